@@ -0,0 +1,49 @@
+use std::sync::atomic::Ordering;
+
+use gccjit_rs::*;
+
+use atomics::*;
+use ctx::*;
+use function::*;
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    let int = ctx.new_type::<i64>();
+    let counter = ctx.new_global(None, GlobalKind::Exported, int, "counter");
+    let counter = Atomic::new(counter);
+
+    // long bump(long n) { return atomic_fetch_add(&counter, n); }
+    let n = ctx.new_parameter(None, int, "n");
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[n], "bump", false);
+    let block = func.new_block("entry");
+    let old = counter.fetch_add(None, func.get_param(0), Ordering::SeqCst);
+    block.end_with_return(None, old);
+
+    // long set_max(long n): a compare-exchange loop storing max(counter, n).
+    let n = ctx.new_parameter(None, int, "n");
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[n], "set_max", false);
+    let n = func.get_param(0);
+    let seen = func.new_local(None, int, "seen");
+    let entry = func.new_block("entry");
+    let check = func.new_block("check");
+    let retry = func.new_block("retry");
+    let done = func.new_block("done");
+    entry.add_assignment(None, seen, counter.load(None, Ordering::Relaxed));
+    entry.end_with_jump(None, check);
+    let bigger = ctx.new_comparison(None, block::ComparisonOp::LessThan, seen, n);
+    check.end_with_conditional(None, bigger, retry, done);
+    let swapped = counter.compare_exchange_weak(None, seen, n, Ordering::AcqRel, Ordering::Relaxed);
+    retry.end_with_conditional(None, swapped, done, check);
+    fence(done, None, Ordering::SeqCst);
+    done.end_with_return(None, counter.load(None, Ordering::Acquire));
+
+    let result = ctx.compile();
+    let bump: extern "C" fn(i64) -> i64 = unsafe { std::mem::transmute(result.get_function("bump")) };
+    let set_max: extern "C" fn(i64) -> i64 = unsafe { std::mem::transmute(result.get_function("set_max")) };
+    assert_eq!(bump(5), 0);
+    assert_eq!(bump(2), 5);
+    assert_eq!(set_max(3), 7);
+    assert_eq!(set_max(40), 40);
+}
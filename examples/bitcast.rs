@@ -0,0 +1,30 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    // uint32_t float_bits(float f) { return bit_cast<uint32_t>(f); }
+    let float = ctx.new_type::<f32>();
+    let u32_ty = ctx.new_type::<u32>();
+    let param = ctx.new_parameter(None, float, "f");
+    let func = ctx.new_function(None, FunctionType::Exported, u32_ty, &[param], "float_bits", false);
+    let block = func.new_block("entry");
+    block.end_with_return(None, ctx.new_bitcast(None, func.get_param(0), u32_ty));
+
+    // int double_size(void) { return sizeof(double) + _Alignof(double); }
+    let int = ctx.new_type::<i32>();
+    let double = ctx.new_type::<f64>();
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[], "double_size", false);
+    let block = func.new_block("entry");
+    block.end_with_return(None, ctx.new_sizeof(double) + ctx.new_alignof(double));
+
+    let result = ctx.compile();
+    let float_bits: extern "C" fn(f32) -> u32 = unsafe { std::mem::transmute(result.get_function("float_bits")) };
+    let double_size: extern "C" fn() -> i32 = unsafe { std::mem::transmute(result.get_function("double_size")) };
+    assert_eq!(float_bits(1.5), 1.5f32.to_bits());
+    assert_eq!(double_size(), 16);
+}
@@ -0,0 +1,45 @@
+use gccjit_rs::*;
+
+use block::BinaryOp;
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+
+fn main() {
+    let ctx = Context::default();
+    let uint = ctx.new_type::<u32>();
+
+    // struct control { unsigned enable : 1; unsigned mode : 3; unsigned count : 12; };
+    let enable = ctx.new_bitfield(None, uint, 1, "enable");
+    let mode = ctx.new_bitfield(None, uint, 3, "mode");
+    let count = ctx.new_bitfield(None, uint, 12, "count");
+    let control = ctx.new_struct_type(None, "control", &[enable, mode, count]);
+
+    // unsigned bump(struct control *reg, unsigned new_mode)
+    // {
+    //   reg->enable = 1;
+    //   reg->mode = new_mode;
+    //   reg->count += 1;
+    //   return reg->count;
+    // }
+    let reg = ctx.new_parameter(None, control.as_type().make_pointer(), "reg");
+    let new_mode = ctx.new_parameter(None, uint, "new_mode");
+    let bump = ctx.new_function(None, FunctionType::Exported, uint, &[reg, new_mode], "bump", false);
+    let block = bump.new_block("entry");
+    let reg = reg.to_rvalue();
+    block.add_assignment(None, reg.dereference_field(None, enable), ctx.new_rvalue_one(uint));
+    block.add_assignment(None, reg.dereference_field(None, mode), new_mode);
+    block.add_assignment_op(
+        None,
+        reg.dereference_field(None, count),
+        BinaryOp::Plus,
+        ctx.new_rvalue_one(uint),
+    );
+    block.end_with_return(None, reg.dereference_field(None, count).to_rvalue());
+
+    let result = ctx.compile();
+    let bump: extern "C" fn(*mut u32, u32) -> u32 = unsafe { std::mem::transmute(result.get_function("bump")) };
+    let mut bits = 41 << 4;
+    assert_eq!(bump(&mut bits, 5), 42);
+    assert_eq!(bits, 42 << 4 | 5 << 1 | 1);
+}
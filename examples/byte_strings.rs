@@ -0,0 +1,33 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    // A format string with embedded NULs, as a literal, and a binary blob
+    // without a terminator, copied into a global in one go.
+    let format = b"name\0age\0%d\n";
+    let blob = [0xde, 0xad, 0x00, 0xbe, 0xef];
+
+    let literal = ctx.new_byte_string_literal(format, true);
+    let global = ctx.new_global(None, GlobalKind::Exported, literal.get_type(), "format");
+    global.set_initializer_rvalue(literal);
+
+    let global = ctx.new_byte_string_global(None, GlobalKind::Exported, "blob", &blob, false);
+
+    // unsigned char blob_byte(int i) { return blob[i]; }
+    let int = ctx.new_type::<i32>();
+    let param = ctx.new_parameter(None, int, "i");
+    let func = ctx.new_function(None, FunctionType::Exported, ctx.new_type::<u8>(), &[param], "blob_byte", false);
+    let block = func.new_block("entry");
+    block.end_with_return(None, ctx.new_array_access(None, global, func.get_param(0)));
+
+    let result = ctx.compile();
+    let blob_byte: extern "C" fn(i32) -> u8 = unsafe { std::mem::transmute(result.get_function("blob_byte")) };
+    let format_ptr = result.get_global("format") as *const [u8; 13];
+    assert_eq!(unsafe { *format_ptr }, *b"name\0age\0%d\n\0");
+    assert_eq!((0..5).map(|i| blob_byte(i)).collect::<Vec<_>>(), blob);
+}
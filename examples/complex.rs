@@ -0,0 +1,25 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+use ty::Complex;
+
+fn main() {
+    let ctx = Context::default();
+    let complex = ctx.new_type::<Complex<f64>>();
+    let double = ctx.new_type::<f64>();
+
+    // double rotate_real(_Complex double z) { return creal(z * (0 + 1i) + 0.5); }
+    let z = ctx.new_parameter(None, complex, "z");
+    let func = ctx.new_function(None, FunctionType::Exported, double, &[z], "rotate_real", false);
+    let block = func.new_block("entry");
+    let i = ctx.new_rvalue_from_complex(complex, Complex::new(0.0, 1.0));
+    let rotated = z.to_rvalue() * i + ctx.new_rvalue_from_double(double, 0.5);
+    block.end_with_return(None, rotated.real(None));
+
+    let result = ctx.compile();
+    let rotate_real: extern "C" fn(Complex<f64>) -> f64 =
+        unsafe { std::mem::transmute(result.get_function("rotate_real")) };
+    assert_eq!(rotate_real(Complex::new(1.0, 2.0)), -1.5);
+}
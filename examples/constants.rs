@@ -0,0 +1,43 @@
+use gccjit_rs::*;
+
+use constant::IntoConst;
+use ctx::*;
+use function::*;
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    // Every function returns one constant.
+    let constant = |name: &str, ty, value| {
+        let func = ctx.new_function(None, FunctionType::Exported, ty, &[], name, false);
+        func.new_block("entry").end_with_return(None, value);
+    };
+    let u64_ty = ctx.new_type::<u64>();
+    let u128_ty = ctx.new_type::<u128>();
+    let f32_ty = ctx.new_type::<f32>();
+    let f64_ty = ctx.new_type::<f64>();
+    constant("big", u64_ty, ctx.new_rvalue_from_u64(u64_ty, u64::MAX - 1));
+    constant("hash", u128_ty, ctx.new_const(0x0123_4567_89ab_cdef_fedc_ba98_7654_3210u128));
+    constant("negative", ctx.new_type::<i128>(), ctx.new_rvalue_from_i128(ctx.new_type::<i128>(), -(1 << 100)));
+    constant("nan", f32_ty, ctx.new_rvalue_from_f32(f32_ty, f32::from_bits(0x7fc0_1234)));
+    constant("neg_zero", f64_ty, ctx.new_const(-0.0f64));
+    constant("inf", f64_ty, f64::NEG_INFINITY.into_const(&ctx, f64_ty));
+
+    let result = ctx.compile();
+    unsafe {
+        let get = |name| result.get_function(name);
+        let big: extern "C" fn() -> u64 = std::mem::transmute(get("big"));
+        let hash: extern "C" fn() -> u128 = std::mem::transmute(get("hash"));
+        let negative: extern "C" fn() -> i128 = std::mem::transmute(get("negative"));
+        let nan: extern "C" fn() -> f32 = std::mem::transmute(get("nan"));
+        let neg_zero: extern "C" fn() -> f64 = std::mem::transmute(get("neg_zero"));
+        let inf: extern "C" fn() -> f64 = std::mem::transmute(get("inf"));
+        assert_eq!(big(), u64::MAX - 1);
+        assert_eq!(hash(), 0x0123_4567_89ab_cdef_fedc_ba98_7654_3210);
+        assert_eq!(negative(), -(1 << 100));
+        assert_eq!(nan().to_bits(), 0x7fc0_1234);
+        assert_eq!(neg_zero().to_bits(), (-0.0f64).to_bits());
+        assert_eq!(inf(), f64::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,63 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+struct Config {
+    width: i32,
+    height: i32,
+    depth: i32,
+    scale: f64,
+}
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    let int = ctx.new_type::<i32>();
+    let double = ctx.new_type::<f64>();
+    let width = ctx.new_field(None, int, "width");
+    let height = ctx.new_field(None, int, "height");
+    let depth = ctx.new_field(None, int, "depth");
+    let scale = ctx.new_field(None, double, "scale");
+    let config = ctx.new_struct_type(None, "config", &[width, height, depth, scale]).as_type();
+
+    // struct config default_config(int w)
+    // {
+    //     return (struct config){ .scale = 1.5, .width = w };
+    // }
+    let param = ctx.new_parameter(None, int, "w");
+    let func = ctx.new_function(None, FunctionType::Exported, config, &[param], "default_config", false);
+    let block = func.new_block("entry");
+    let value = ctx.new_struct_constructor(
+        None,
+        config,
+        &[
+            (scale, ctx.new_rvalue_from_double(double, 1.5)),
+            (width, func.get_param(0).to_rvalue()),
+        ],
+    );
+    block.end_with_return(None, value);
+
+    // int third(void) { return ((int[4]){ 7, 8, 9 })[2]; }
+    let array = ctx.new_array_type(None, int, 4);
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[], "third", false);
+    let block = func.new_block("entry");
+    let elements: Vec<_> = (7..10).map(|i| ctx.new_rvalue_from_int(int, i)).collect();
+    let local = func.new_local(None, array, "values");
+    block.add_assignment(None, local, ctx.new_array_constructor(None, array, &elements));
+    block.end_with_return(None, ctx.new_array_access(None, local, ctx.new_rvalue_from_int(int, 2)));
+
+    let result = ctx.compile();
+    let default_config: extern "C" fn(i32) -> Config =
+        unsafe { std::mem::transmute(result.get_function("default_config")) };
+    let third: extern "C" fn() -> i32 = unsafe { std::mem::transmute(result.get_function("third")) };
+    assert_eq!(
+        default_config(640),
+        Config { width: 640, height: 0, depth: 0, scale: 1.5 }
+    );
+    assert_eq!(third(), 9);
+}
@@ -0,0 +1,71 @@
+use gccjit_rs::*;
+
+use builder::FunctionBuilder;
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    let int = ctx.new_type::<i32>();
+
+    // int collatz(int n): the number of steps for n to reach 1.
+    let n = ctx.new_parameter(None, int, "n");
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[n], "collatz", false);
+    let mut builder = FunctionBuilder::new(func);
+    let n = func.new_local(None, int, "value");
+    let mut steps = func.new_local(None, int, "steps");
+    builder.block().add_assignment(None, n, func.get_param(0));
+    builder.block().add_assignment(None, steps, ctx.new_rvalue_zero(int));
+    builder.while_loop(None, n.to_rvalue().ne(1), |b| {
+        b.if_then_else(
+            None,
+            n % 2,
+            |b| b.block().add_assignment(None, n, n * 3 + 1),
+            |b| b.block().add_assignment(None, n, n / 2),
+        );
+        // Compound assignments go to the builder's current block.
+        steps += 1;
+    });
+    builder.return_(None, steps);
+    builder.finish();
+
+    // int sum_odd_below(int limit): skips even numbers with continue and
+    // stops at 100 with break.
+    let limit = ctx.new_parameter(None, int, "limit");
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[limit], "sum_odd_below", false);
+    let mut builder = FunctionBuilder::new(func);
+    let total = func.new_local(None, int, "total");
+    builder.block().add_assignment(None, total, ctx.new_rvalue_zero(int));
+    builder.for_range(None, "i", ctx.new_rvalue_zero(int), func.get_param(0), |b, i| {
+        b.if_then(None, i.ge(100), |b| b.break_(None));
+        b.if_then(None, (i % 2).eq(0), |b| b.continue_(None));
+        b.block().add_assignment(None, total, total + i);
+    });
+    builder.return_(None, total);
+    builder.finish();
+
+    // int first_square_above(int n): a loop that only ends by returning.
+    let n = ctx.new_parameter(None, int, "n");
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[n], "first_square_above", false);
+    let mut builder = FunctionBuilder::new(func);
+    let root = func.new_local(None, int, "root");
+    builder.block().add_assignment(None, root, ctx.new_rvalue_zero(int));
+    builder.loop_(None, |b| {
+        b.if_then(None, (root * root).gt(func.get_param(0)), |b| b.return_(None, root * root));
+        b.block().add_assignment(None, root, root + 1);
+    });
+    builder.finish();
+
+    let result = ctx.compile();
+    let collatz: extern "C" fn(i32) -> i32 = unsafe { std::mem::transmute(result.get_function("collatz")) };
+    let sum_odd_below: extern "C" fn(i32) -> i32 = unsafe { std::mem::transmute(result.get_function("sum_odd_below")) };
+    let first_square_above: extern "C" fn(i32) -> i32 =
+        unsafe { std::mem::transmute(result.get_function("first_square_above")) };
+    assert_eq!(collatz(27), 111);
+    assert_eq!(sum_odd_below(10), 1 + 3 + 5 + 7 + 9);
+    assert_eq!(sum_odd_below(1000), (1..100).step_by(2).sum::<i32>());
+    assert_eq!(first_square_above(50), 64);
+}
@@ -0,0 +1,59 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+use ty::Typeable;
+
+#[derive(Typeable)]
+#[repr(C)]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Typeable)]
+#[repr(C)]
+pub struct Node {
+    value: i64,
+    next: *mut Node,
+}
+
+#[derive(Typeable)]
+#[repr(C)]
+pub union Bits {
+    f: f64,
+    u: u64,
+}
+
+#[derive(Typeable, Copy, Clone)]
+#[repr(u8)]
+pub enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    let point = ctx.new_type::<Point>();
+    let node_ptr = ctx.new_type::<*mut Node>();
+    println!("{:?} {:?} {:?} {:?}", point, node_ptr, ctx.new_type::<Bits>(), ctx.new_type::<Color>());
+
+    // Color next_color(Color c) { return (c + 1) % 3; }
+    let color = ctx.new_type::<Color>();
+    let param = ctx.new_parameter(None, color, "c");
+    let next = ctx.new_function(None, FunctionType::Exported, color, &[param], "next_color", false);
+    let block = next.new_block("entry");
+    let c = next.get_param(0).to_rvalue();
+    block.end_with_return(
+        None,
+        (c + ctx.new_rvalue_one(color)) % ctx.new_rvalue_from_int(color, 3),
+    );
+
+    let result = ctx.compile();
+    let next: extern "C" fn(Color) -> Color = unsafe { std::mem::transmute(result.get_function("next_color")) };
+    assert_eq!(next(Color::Blue) as u8, Color::Red as u8);
+}
@@ -0,0 +1,46 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+
+fn main() {
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+    let opcode = ctx.new_enum_type("opcode", ctx.new_type::<u8>(), &[("ADD", 0), ("SUB", 1), ("HALT", 255)]);
+    println!("{:?}", opcode);
+
+    // int weight(enum opcode op)
+    // {
+    //   switch (op) {
+    //     case ADD: case SUB: return 1;
+    //     case HALT: return 0;
+    //     default: return -1;
+    //   }
+    // }
+    let op = ctx.new_parameter(None, opcode.as_type(), "op");
+    let weight = ctx.new_function(None, FunctionType::Exported, int, &[op], "weight", false);
+    let entry = weight.new_block("entry");
+    let arith = weight.new_block("arith");
+    let halt = weight.new_block("halt");
+    let other = weight.new_block("other");
+    entry.end_with_switch(
+        None,
+        op,
+        other,
+        vec![
+            opcode.new_case("ADD", arith),
+            opcode.new_case("SUB", arith),
+            opcode.new_case("HALT", halt),
+        ],
+    );
+    arith.end_with_return(None, ctx.new_rvalue_one(int));
+    halt.end_with_return(None, ctx.new_rvalue_zero(int));
+    other.end_with_return(None, ctx.new_rvalue_from_int(int, -1));
+
+    let result = ctx.compile();
+    let weight: extern "C" fn(u8) -> i32 = unsafe { std::mem::transmute(result.get_function("weight")) };
+    assert_eq!(weight(1), 1);
+    assert_eq!(weight(255), 0);
+    assert_eq!(weight(7), -1);
+    println!("{}", opcode.name_of(255).unwrap());
+}
@@ -0,0 +1,72 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    let int = ctx.new_type::<i32>();
+    let float = ctx.new_type::<f32>();
+
+    // struct point { int x; int y; };
+    let x = ctx.new_field(None, int, "x");
+    let y = ctx.new_field(None, int, "y");
+    let point = ctx.new_struct_type(None, "point", &[x, y]);
+
+    // struct node { int value; struct node *next; }, through set_fields.
+    let node = ctx.new_opaque_struct_type(None, "node");
+    let value = ctx.new_field(None, int, "value");
+    let next = ctx.new_field(None, node.as_type().make_pointer(), "next");
+    let node = node.set_fields(None, &[value, next]);
+
+    // union bits { int i; float f; };
+    let i = ctx.new_field(None, int, "i");
+    let f = ctx.new_field(None, float, "f");
+    let bits = ctx.new_union_type(None, "bits", &[i, f]);
+    assert_eq!(bits.fields().len(), 2);
+
+    // int sum(struct point p) { return p.x + p.y; }
+    let p = ctx.new_parameter(None, point.as_type(), "p");
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[p], "sum", false);
+    let block = func.new_block("entry");
+    let p = func.get_param(0).to_rvalue();
+    block.end_with_return(None, p.field(None, "x") + p.field(None, "y"));
+
+    // int second(struct node *n) { return n->next->value; }
+    let n = ctx.new_parameter(None, node.as_type().make_pointer(), "n");
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[n], "second", false);
+    let block = func.new_block("entry");
+    let next = func.get_param(0).to_rvalue().deref_field(None, "next");
+    block.end_with_return(None, next.to_rvalue().deref_field(None, "value"));
+
+    // int float_bits(float value) { union bits b; b.f = value; return b.i; }
+    let value = ctx.new_parameter(None, float, "value");
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[value], "float_bits", false);
+    let block = func.new_block("entry");
+    let b = func.new_local(None, bits.as_type(), "b");
+    block.add_assignment(None, b.field(None, "f"), func.get_param(0));
+    block.end_with_return(None, b.field(None, "i"));
+
+    let result = ctx.compile();
+    #[repr(C)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+    #[repr(C)]
+    struct Node {
+        value: i32,
+        next: *mut Node,
+    }
+    let sum: extern "C" fn(Point) -> i32 = unsafe { std::mem::transmute(result.get_function("sum")) };
+    let second: extern "C" fn(*mut Node) -> i32 = unsafe { std::mem::transmute(result.get_function("second")) };
+    let float_bits: extern "C" fn(f32) -> i32 = unsafe { std::mem::transmute(result.get_function("float_bits")) };
+    assert_eq!(sum(Point { x: 40, y: 2 }), 42);
+    let mut tail = Node { value: 7, next: std::ptr::null_mut() };
+    let mut head = Node { value: 1, next: &mut tail };
+    assert_eq!(second(&mut head), 7);
+    assert_eq!(float_bits(1.5), 1.5f32.to_bits() as i32);
+}
@@ -0,0 +1,55 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use ty::Typeable;
+
+#[derive(Typeable, Copy, Clone)]
+#[repr(C)]
+pub struct Limits {
+    min: i16,
+    max: i64,
+    scale: f32,
+}
+
+fn crc32_table() -> Vec<u32> {
+    (0..256u32)
+        .map(|i| (0..8).fold(i, |c, _| if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 }))
+        .collect()
+}
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    // A lookup table from raw bytes.
+    let u32_ty = ctx.new_type::<u32>();
+    let table_ty = ctx.new_array_type(None, u32_ty, 256);
+    let table = ctx.new_global(None, GlobalKind::Exported, table_ty, "crc32_table");
+    let bytes: Vec<u8> = crc32_table().iter().flat_map(|v| v.to_ne_bytes()).collect();
+    table.set_initializer(&bytes);
+
+    // A constant expression.
+    let int = ctx.new_type::<i32>();
+    let primes_ty = ctx.new_array_type(None, int, 8);
+    let primes = ctx.new_global(None, GlobalKind::Exported, primes_ty, "primes");
+    let values: Vec<_> = [2, 3, 5, 7, 11].iter().map(|&p| ctx.new_rvalue_from_int(int, p)).collect();
+    primes.set_initializer_rvalue(ctx.new_array_constructor(None, primes_ty, &values));
+
+    // A Rust value.
+    ctx.new_global_with_value(
+        None,
+        GlobalKind::Exported,
+        "limits",
+        Limits { min: -3, max: 1 << 40, scale: 0.25 },
+    );
+
+    let result = ctx.compile();
+    let table = result.get_global("crc32_table") as *const [u32; 256];
+    let primes = result.get_global("primes") as *const [i32; 8];
+    let limits = result.get_global("limits") as *const Limits;
+    unsafe {
+        assert_eq!((&*table)[..], crc32_table()[..]);
+        assert_eq!(*primes, [2, 3, 5, 7, 11, 0, 0, 0]);
+        assert_eq!(((*limits).min, (*limits).max, (*limits).scale), (-3, 1 << 40, 0.25));
+    }
+}
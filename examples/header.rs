@@ -0,0 +1,44 @@
+use gccjit_rs::*;
+
+use cdecl::CDecl;
+use ctx::*;
+use function::*;
+
+fn main() {
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+    let char_ptr = ctx.new_type::<*const char>();
+
+    // typedef int (*handler)(const char *);
+    let handler = ctx.new_function_pointer_type(None, int, &[char_ptr], false);
+    let table = ctx.new_array_type(None, handler, 4);
+
+    let name = ctx.new_field(None, char_ptr, "name");
+    let handlers = ctx.new_field(None, table, "handlers");
+    let flags = ctx.new_bitfield(None, ctx.new_type::<u32>(), 3, "flags");
+    let registry = ctx.new_struct_type(None, "registry", &[name, handlers, flags]);
+
+    let lookup = ctx.new_function(
+        None,
+        FunctionType::Extern,
+        handler,
+        &[
+            ctx.new_parameter(None, registry.as_type().make_pointer(), "reg"),
+            ctx.new_parameter(None, char_ptr, "name"),
+        ],
+        "lookup",
+        false,
+    );
+    let global = ctx.new_global(None, GlobalKind::External, table.make_pointer(), "current");
+
+    // struct registry {
+    //     const char *name;
+    //     int (*handlers[4])(const char *);
+    //     unsigned int flags : 3;
+    // };
+    // extern int (*lookup(struct registry *reg, const char *name))(const char *);
+    // extern int (*(*current)[4])(const char *);
+    println!("{}", registry.c_decl());
+    println!("{}", lookup.c_decl());
+    println!("{}", global.c_decl());
+}
@@ -0,0 +1,25 @@
+use gccjit_rs::*;
+
+use ctx::*;
+
+#[repr(C)]
+pub struct Header {
+    tag: u8,
+    len: u32,
+    next: *mut Header,
+}
+
+fn main() {
+    let ctx = Context::default();
+
+    let header = ctx.new_opaque_struct_type(None, "header");
+    let tag = ctx.new_field(None, ctx.new_type::<u8>(), "tag");
+    let len = ctx.new_field(None, ctx.new_type::<u32>(), "len");
+    let next = ctx.new_field(None, header.as_type().make_pointer(), "next");
+    let header = header.set_fields(None, &[tag, len, next]);
+
+    println!("u32: {}", layout::type_layout(ctx.new_type::<u32>()));
+    println!("{:?}", layout::struct_layout(header, &[tag, len, next]));
+
+    assert_layout!(Header, header, { tag: tag, len: len, next: next });
+}
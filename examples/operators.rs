@@ -0,0 +1,44 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    // long poly(long x)
+    // {
+    //     long acc = 3 * x * x - x;
+    //     acc += 7;
+    //     acc <<= 1;
+    //     if (acc > 100 && x != 0) return -acc;
+    //     return ~acc;
+    // }
+    let long = ctx.new_type::<i64>();
+    let param = ctx.new_parameter(None, long, "x");
+    let func = ctx.new_function(None, FunctionType::Exported, long, &[param], "poly", false);
+    let x = func.get_param(0).to_rvalue();
+
+    let entry = func.new_block("entry");
+    let big = func.new_block("big");
+    let small = func.new_block("small");
+    let mut acc = func.new_local(None, long, "acc");
+    entry.add_assignment(None, acc, 3 * x * x - x);
+
+    ctx.set_current_block(Some(entry));
+    acc += 7;
+    acc <<= 1;
+    ctx.set_current_block(None);
+
+    let cond = acc.to_rvalue().gt(100).and(x.ne(0));
+    entry.end_with_conditional(None, cond, big, small);
+    big.end_with_return(None, -acc);
+    small.end_with_return(None, !acc);
+
+    let result = ctx.compile();
+    let poly: extern "C" fn(i64) -> i64 = unsafe { std::mem::transmute(result.get_function("poly")) };
+    assert_eq!(poly(1), !18);
+    assert_eq!(poly(10), -(2 * (300 - 10 + 7)));
+}
@@ -0,0 +1,58 @@
+use gccjit_rs::*;
+
+use block::BinaryOp;
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    let int = ctx.new_type::<i32>();
+    let long = ctx.new_type::<i64>();
+    let boolean = ctx.new_type::<bool>();
+
+    // bool mul_overflows(int a, int b, int *out)
+    let a = ctx.new_parameter(None, int, "a");
+    let b = ctx.new_parameter(None, int, "b");
+    let out = ctx.new_parameter(None, int.make_pointer(), "out");
+    let func = ctx.new_function(None, FunctionType::Exported, boolean, &[a, b, out], "mul_overflows", false);
+    let block = func.new_block("entry");
+    let (result, overflowed) =
+        ctx.new_overflowing_op(block, None, BinaryOp::Mult, func.get_param(0), func.get_param(1), int);
+    block.add_assignment(None, func.get_param(2).to_rvalue().dereference(None), result);
+    block.end_with_return(None, overflowed);
+
+    // long checked_add(long a, long b) { return a + b, or -1 on overflow; }
+    let a = ctx.new_parameter(None, long, "a");
+    let b = ctx.new_parameter(None, long, "b");
+    let func = ctx.new_function(None, FunctionType::Exported, long, &[a, b], "checked_add", false);
+    let entry = func.new_block("entry");
+    let trap = func.new_block("trap");
+    let ok = func.new_block("ok");
+    let sum = ctx.new_checked_op(
+        entry,
+        None,
+        BinaryOp::Plus,
+        func.get_param(0),
+        func.get_param(1),
+        long,
+        trap,
+        ok,
+    );
+    trap.end_with_return(None, ctx.new_rvalue_from_long(long, -1));
+    ok.end_with_return(None, sum);
+
+    let result = ctx.compile();
+    let mul_overflows: extern "C" fn(i32, i32, *mut i32) -> bool =
+        unsafe { std::mem::transmute(result.get_function("mul_overflows")) };
+    let checked_add: extern "C" fn(i64, i64) -> i64 = unsafe { std::mem::transmute(result.get_function("checked_add")) };
+    let mut out = 0;
+    assert!(!mul_overflows(1000, 1000, &mut out));
+    assert_eq!(out, 1_000_000);
+    assert!(mul_overflows(1 << 20, 1 << 12, &mut out));
+    assert_eq!(out, (1i32 << 20).wrapping_mul(1 << 12));
+    assert_eq!(checked_add(40, 2), 42);
+    assert_eq!(checked_add(i64::MAX, 1), -1);
+}
@@ -0,0 +1,58 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    let int_ptr = ctx.new_type::<*mut i32>();
+    let usize_ty = ctx.new_type::<usize>();
+    let isize_ty = ctx.new_type::<isize>();
+
+    // int *third(int *p) { return p + 2; }
+    let p = ctx.new_parameter(None, int_ptr, "p");
+    let func = ctx.new_function(None, FunctionType::Exported, int_ptr, &[p], "third", false);
+    let block = func.new_block("entry");
+    let two = ctx.new_rvalue_from_int(ctx.new_type::<i32>(), 2);
+    block.end_with_return(None, func.get_param(0).to_rvalue().ptr_offset(None, two));
+
+    // isize distance(int *end, int *start) { return end.offset_from(start); }
+    let end = ctx.new_parameter(None, int_ptr, "end");
+    let start = ctx.new_parameter(None, int_ptr, "start");
+    let func = ctx.new_function(None, FunctionType::Exported, isize_ty, &[end, start], "distance", false);
+    let block = func.new_block("entry");
+    block.end_with_return(None, func.get_param(0).to_rvalue().ptr_diff(None, func.get_param(1)));
+
+    // A bump allocator step: align the address up to 16, then skip n bytes.
+    let p = ctx.new_parameter(None, int_ptr, "p");
+    let n = ctx.new_parameter(None, usize_ty, "n");
+    let func = ctx.new_function(None, FunctionType::Exported, int_ptr, &[p, n], "bump", false);
+    let block = func.new_block("entry");
+    let aligned = func.get_param(0).to_rvalue().align_up(None, 16);
+    block.end_with_return(None, aligned.ptr_byte_add(None, func.get_param(1)));
+
+    // usize round_trip(usize n) { return (usize)(int *)n; }
+    let n = ctx.new_parameter(None, usize_ty, "n");
+    let func = ctx.new_function(None, FunctionType::Exported, usize_ty, &[n], "round_trip", false);
+    let block = func.new_block("entry");
+    let ptr = func.get_param(0).to_rvalue().usize_to_ptr(None, int_ptr);
+    block.end_with_return(None, ptr.ptr_to_usize(None));
+
+    let result = ctx.compile();
+    let third: extern "C" fn(*mut i32) -> *mut i32 = unsafe { std::mem::transmute(result.get_function("third")) };
+    let distance: extern "C" fn(*mut i32, *mut i32) -> isize =
+        unsafe { std::mem::transmute(result.get_function("distance")) };
+    let bump: extern "C" fn(*mut i32, usize) -> *mut i32 = unsafe { std::mem::transmute(result.get_function("bump")) };
+    let round_trip: extern "C" fn(usize) -> usize = unsafe { std::mem::transmute(result.get_function("round_trip")) };
+
+    let mut array = [1, 2, 3, 4];
+    let base = array.as_mut_ptr();
+    assert_eq!(third(base), base.wrapping_add(2));
+    assert_eq!(distance(base.wrapping_add(3), base), 3);
+    assert_eq!(distance(base, base.wrapping_add(3)), -3);
+    assert_eq!(bump(0x1001 as *mut i32, 8) as usize, 0x1018);
+    assert_eq!(round_trip(0xdead_beef), 0xdead_beef);
+}
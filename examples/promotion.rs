@@ -0,0 +1,46 @@
+use gccjit_rs::*;
+
+use block::{BinaryOp, ComparisonOp};
+use ctx::*;
+use function::*;
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    // double mix(unsigned char a, int b, float c)
+    // {
+    //     if (a) return a * b + c;
+    //     return (b < 2u) + c;
+    // }
+    let params = [
+        ctx.new_parameter(None, ctx.new_type::<u8>(), "a"),
+        ctx.new_parameter(None, ctx.new_type::<i32>(), "b"),
+        ctx.new_parameter(None, ctx.new_type::<f32>(), "c"),
+    ];
+    let double = ctx.new_type::<f64>();
+    let func = ctx.new_function(None, FunctionType::Exported, double, &params, "mix", false);
+    let (a, b, c) = (func.get_param(0), func.get_param(1), func.get_param(2));
+
+    let entry = func.new_block("entry");
+    let nonzero = func.new_block("nonzero");
+    let zero = func.new_block("zero");
+    entry.end_with_promoted_conditional(None, a, nonzero, zero);
+
+    // unsigned char * int is int, and int + float is float.
+    let product = ctx.new_promoted_binary_op(None, BinaryOp::Mult, a, b);
+    let sum = ctx.new_promoted_binary_op(None, BinaryOp::Plus, product, c);
+    nonzero.end_with_return(None, ctx.new_cast(None, sum, double));
+
+    // int < unsigned compares as unsigned, so -1 < 2u is false.
+    let two = ctx.new_rvalue_from_int(ctx.new_type::<u32>(), 2);
+    let less = ctx.new_promoted_comparison(None, ComparisonOp::LessThan, b, two);
+    let sum = ctx.new_promoted_binary_op(None, BinaryOp::Plus, ctx.new_cast(None, less, ctx.new_type::<i32>()), c);
+    zero.end_with_return(None, ctx.new_cast(None, sum, double));
+
+    let result = ctx.compile();
+    let mix: extern "C" fn(u8, i32, f32) -> f64 = unsafe { std::mem::transmute(result.get_function("mix")) };
+    assert_eq!(mix(3, 4, 0.5), 12.5);
+    assert_eq!(mix(0, 1, 0.5), 1.5);
+    assert_eq!(mix(0, -1, 0.5), 0.5);
+}
@@ -0,0 +1,36 @@
+use gccjit_rs::*;
+
+use cdecl::CDecl;
+use ctx::*;
+
+fn main() {
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+
+    // struct tree { struct node *root; int size; };
+    // struct node { struct tree *owner; struct node *left, *right; int key; };
+    let structs = ctx.new_recursive_struct_types(None, &["tree", "node"], |types| {
+        let (tree, node) = (types[0].make_pointer(), types[1].make_pointer());
+        vec![
+            vec![
+                ctx.new_field(None, node, "root"),
+                ctx.new_field(None, int, "size"),
+            ],
+            vec![
+                ctx.new_field(None, tree, "owner"),
+                ctx.new_field(None, node, "left"),
+                ctx.new_field(None, node, "right"),
+                ctx.new_field(None, int, "key"),
+            ],
+        ]
+    });
+    for s in structs.iter() {
+        println!("{}", s.c_decl());
+    }
+
+    // FILE-like handles that jitted code never looks inside.
+    let handle = ctx.new_opaque_struct_type(None, "handle").keep_opaque();
+    println!("{}", handle.c_decl());
+
+    ctx.compile();
+}
@@ -0,0 +1,63 @@
+use gccjit_rs::*;
+
+use arith::RustArith;
+use ctx::*;
+use function::*;
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+    let arith = RustArith::new(&ctx);
+
+    let int = ctx.new_type::<i32>();
+    let byte = ctx.new_type::<u8>();
+
+    let binary = |name: &str, ty| {
+        let a = ctx.new_parameter(None, ty, "a");
+        let b = ctx.new_parameter(None, ty, "b");
+        let func = ctx.new_function(None, FunctionType::Exported, ty, &[a, b], name, false);
+        (func, func.new_block("entry"))
+    };
+
+    let (func, block) = binary("wrapping_add", int);
+    block.end_with_return(None, arith.wrapping_add(None, func.get_param(0), func.get_param(1)));
+
+    let (func, block) = binary("wrapping_shl", int);
+    block.end_with_return(None, arith.wrapping_shl(None, func.get_param(0), func.get_param(1)));
+
+    let (func, block) = binary("saturating_add", int);
+    let sum = arith.saturating_add(block, None, func.get_param(0), func.get_param(1));
+    block.end_with_return(None, sum);
+
+    let (func, block) = binary("saturating_sub", byte);
+    let difference = arith.saturating_sub(block, None, func.get_param(0), func.get_param(1));
+    block.end_with_return(None, difference);
+
+    let (func, block) = binary("saturating_mul", int);
+    let product = arith.saturating_mul(block, None, func.get_param(0), func.get_param(1));
+    block.end_with_return(None, product);
+
+    let (func, block) = binary("div", int);
+    let (quotient, block) = arith.div(block, None, func.get_param(0), func.get_param(1));
+    block.end_with_return(None, quotient);
+
+    let result = ctx.compile();
+    let get = |name| result.get_function(name);
+    let wrapping_add: extern "C" fn(i32, i32) -> i32 = unsafe { std::mem::transmute(get("wrapping_add")) };
+    let wrapping_shl: extern "C" fn(i32, i32) -> i32 = unsafe { std::mem::transmute(get("wrapping_shl")) };
+    let saturating_add: extern "C" fn(i32, i32) -> i32 = unsafe { std::mem::transmute(get("saturating_add")) };
+    let saturating_sub: extern "C" fn(u8, u8) -> u8 = unsafe { std::mem::transmute(get("saturating_sub")) };
+    let saturating_mul: extern "C" fn(i32, i32) -> i32 = unsafe { std::mem::transmute(get("saturating_mul")) };
+    let div: extern "C" fn(i32, i32) -> i32 = unsafe { std::mem::transmute(get("div")) };
+
+    for &(a, b) in &[(i32::MAX, 1), (i32::MIN, -1), (-7, 3), (1 << 20, 1 << 12), (i32::MIN, i32::MAX)] {
+        assert_eq!(wrapping_add(a, b), a.wrapping_add(b));
+        assert_eq!(wrapping_shl(a, b), a.wrapping_shl(b as u32));
+        assert_eq!(saturating_add(a, b), a.saturating_add(b));
+        assert_eq!(saturating_mul(a, b), a.saturating_mul(b));
+    }
+    assert_eq!(saturating_sub(3, 5), 0);
+    assert_eq!(saturating_sub(5, 3), 2);
+    assert_eq!(div(-7, 2), -3);
+    // div(1, 0) and div(i32::MIN, -1) trap, like the Rust operator panics.
+}
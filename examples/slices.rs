@@ -0,0 +1,54 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+use slice::SliceType;
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    let int = ctx.new_type::<i32>();
+    let usize_ty = ctx.new_type::<usize>();
+    let ints = SliceType::new(&ctx, int);
+    assert_eq!(ints.as_type(), ctx.new_type::<&[i32]>());
+
+    // int sum(&[int] values): a loop over every element, bounds-checked.
+    let values = ctx.new_parameter(None, ints.as_type(), "values");
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[values], "sum", false);
+    let values = func.get_param(0);
+    let total = func.new_local(None, int, "total");
+    let i = func.new_local(None, usize_ty, "i");
+    let entry = func.new_block("entry");
+    let check = func.new_block("check");
+    let body = func.new_block("body");
+    let done = func.new_block("done");
+    entry.add_assignment(None, total, ctx.new_rvalue_zero(int));
+    entry.add_assignment(None, i, ctx.new_rvalue_zero(usize_ty));
+    entry.end_with_jump(None, check);
+    check.end_with_conditional(None, i.to_rvalue().lt(ints.len(None, values)), body, done);
+    let (element, body) = ints.index(body, None, values, i);
+    body.add_assignment(None, total, total + element);
+    body.add_assignment(None, i, i + 1);
+    body.end_with_jump(None, check);
+    done.end_with_return(None, total);
+
+    // &str middle(&str s) { return &s[1..s.len() - 1]; }
+    let string = ctx.new_type::<&str>();
+    let bytes = SliceType::new(&ctx, ctx.new_type::<u8>());
+    let s = ctx.new_parameter(None, string, "s");
+    let func = ctx.new_function(None, FunctionType::Exported, string, &[s], "middle", false);
+    let block = func.new_block("entry");
+    let s = func.get_param(0);
+    let end = bytes.len(None, s) - 1;
+    let (middle, block) = bytes.subslice(block, None, s, ctx.new_rvalue_one(usize_ty), end);
+    block.end_with_return(None, middle);
+
+    let result = ctx.compile();
+    let sum: extern "C" fn(&[i32]) -> i32 = unsafe { std::mem::transmute(result.get_function("sum")) };
+    let middle: for<'a> extern "C" fn(&'a str) -> &'a str = unsafe { std::mem::transmute(result.get_function("middle")) };
+    assert_eq!(sum(&[1, 2, 3, 36]), 42);
+    assert_eq!(sum(&[]), 0);
+    assert_eq!(middle("(slice)"), "slice");
+}
@@ -0,0 +1,58 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+
+fn main() {
+    let ctx = Context::default();
+
+    // __attribute__((section(".rodata.metadata"))) const int metadata[4] = { 1, 2, 3, 4 };
+    let int = ctx.new_type::<i32>();
+    let table_ty = ctx.new_array_type(None, int, 4);
+    let metadata = ctx.new_global(None, GlobalKind::Exported, table_ty, "metadata");
+    let values: Vec<_> = (1..5).map(|i| ctx.new_rvalue_from_int(int, i)).collect();
+    metadata.set_initializer_rvalue(ctx.new_array_constructor(None, table_ty, &values));
+    metadata.set_link_section(".rodata.metadata");
+
+    // __attribute__((visibility("hidden"))) int helper_state;
+    let state = ctx.new_global(None, GlobalKind::Exported, int, "helper_state");
+    state.set_visibility(Visibility::Hidden);
+
+    // __attribute__((visibility("hidden"))) int helper(int x) { return x * 2; }
+    let x = ctx.new_parameter(None, int, "x");
+    let helper = ctx.new_function(None, FunctionType::Exported, int, &[x], "helper", false);
+    helper.set_visibility(Visibility::Hidden);
+    let block = helper.new_block("entry");
+    block.end_with_return(None, helper.get_param(0).to_rvalue() * 2);
+
+    // int api(int x) { register int tmp asm("ebx") = helper(x); return tmp; }
+    let x = ctx.new_parameter(None, int, "x");
+    let api = ctx.new_function(None, FunctionType::Exported, int, &[x], "api", false);
+    let block = api.new_block("entry");
+    let tmp = api.new_local(None, int, "tmp");
+    if cfg!(target_arch = "x86_64") {
+        tmp.set_register_name("ebx");
+    }
+    let arg = ctx.new_call(None, helper, &[api.get_param(0).to_rvalue()]);
+    block.add_assignment(None, tmp, arg);
+    block.end_with_return(None, tmp);
+
+    // In memory, lookups go through dlsym, which doesn't see hidden symbols.
+    let result = ctx.compile();
+    assert!(result.get_function("helper").is_null());
+    assert!(result.get_global("helper_state").is_null());
+    let api: extern "C" fn(i32) -> i32 = unsafe { std::mem::transmute(result.get_function("api")) };
+    assert_eq!(api(21), 42);
+    let metadata = result.get_global("metadata") as *const [i32; 4];
+    assert_eq!(unsafe { *metadata }, [1, 2, 3, 4]);
+
+    // On disk, `nm -D` lists api and metadata but not helper or
+    // helper_state, and `readelf -S` lists the .rodata.metadata section.
+    let path = std::env::temp_dir().join(format!("libsymbols-{}.so", std::process::id()));
+    ctx.compile_to_file(OutputKind::DynamicLibrary, path.to_str().unwrap());
+    let library = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let section = b".rodata.metadata";
+    assert!(library.windows(section.len()).any(|bytes| bytes == section));
+}
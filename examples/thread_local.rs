@@ -0,0 +1,54 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use lvalue::TlsModel;
+use std::thread;
+
+const MODELS: &[(TlsModel, &str)] = &[
+    (TlsModel::GlobalDynamic, "global_dynamic"),
+    (TlsModel::LocalDynamic, "local_dynamic"),
+    (TlsModel::InitialExec, "initial_exec"),
+];
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    // _Thread_local long global_dynamic, local_dynamic, initial_exec;
+    // void bump(void) { global_dynamic += 1; local_dynamic += 1; initial_exec += 1; }
+    let long = ctx.new_type::<i64>();
+    let func = ctx.new_function(None, FunctionType::Exported, ctx.new_type::<()>(), &[], "bump", false);
+    let block = func.new_block("entry");
+    ctx.set_current_block(Some(block));
+    for &(model, name) in MODELS {
+        let mut counter = ctx.new_global(None, GlobalKind::Exported, long, name);
+        counter.set_tls_model(model);
+        counter += 1;
+    }
+    ctx.set_current_block(None);
+    block.end_with_void_return(None);
+
+    let result = ctx.compile();
+    let bump: extern "C" fn() = unsafe { std::mem::transmute(result.get_function("bump")) };
+
+    // Every thread bumps its counters a different number of times, and must
+    // only ever see its own counts.
+    thread::scope(|scope| {
+        for times in 1..=4i64 {
+            let result = &result;
+            scope.spawn(move || {
+                for _ in 0..times {
+                    bump();
+                }
+                for &(_, name) in MODELS {
+                    let counter = result.get_global(name) as *const i64;
+                    assert_eq!(unsafe { *counter }, times, "{} in thread {}", name, times);
+                }
+            });
+        }
+    });
+    for &(_, name) in MODELS {
+        assert_eq!(unsafe { *(result.get_global(name) as *const i64) }, 0);
+    }
+}
@@ -0,0 +1,44 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use typed::{TypedLValue, TypedRValue};
+
+fn main() {
+    let ctx = Context::default();
+    ctx.set_dump_code(true);
+
+    // int clamp_add(int a, int b, int limit)
+    // {
+    //     int sum = a + b;
+    //     if (sum > limit) return limit;
+    //     return sum;
+    // }
+    let int = ctx.new_type::<i32>();
+    let params = [
+        ctx.new_parameter(None, int, "a"),
+        ctx.new_parameter(None, int, "b"),
+        ctx.new_parameter(None, int, "limit"),
+    ];
+    let func = ctx.new_function(None, FunctionType::Exported, int, &params, "clamp_add", false);
+    let a = TypedRValue::<i32>::from_rvalue(func.get_param(0)).unwrap();
+    let b = TypedRValue::<i32>::from_rvalue(func.get_param(1)).unwrap();
+    let limit = TypedRValue::<i32>::from_rvalue(func.get_param(2)).unwrap();
+
+    let entry = func.new_block("entry");
+    let too_big = func.new_block("too_big");
+    let fits = func.new_block("fits");
+    let sum = TypedLValue::<i32>::from_lvalue(func.new_local(None, int, "sum")).unwrap();
+    sum.assign(entry, None, a + b);
+    // `sum.get() + 1.5` would not compile; mixing types needs a cast.
+    let _as_double: TypedRValue<f64> = sum.get().cast(None);
+    entry.end_with_conditional(None, sum.get().gt(limit), too_big, fits);
+    too_big.end_with_return(None, limit);
+    fits.end_with_return(None, sum);
+
+    let result = ctx.compile();
+    let clamp_add: extern "C" fn(i32, i32, i32) -> i32 =
+        unsafe { std::mem::transmute(result.get_function("clamp_add")) };
+    assert_eq!(clamp_add(1, 2, 10), 3);
+    assert_eq!(clamp_add(8, 9, 10), 10);
+}
@@ -1,51 +1,152 @@
-/*#[macro_use]
-extern crate synstructure;
-#[macro_use]
-extern crate quote;
-extern crate proc_macro2;
+//! `#[derive(Typeable)]` for `#[repr(C)]` structs and unions and for
+//! fieldless enums with an integer representation.
+//!
+//! Fields are emitted in declaration order, which is the order `#[repr(C)]`
+//! lays them out in. Struct types are cached per `Context`, so a type that
+//! refers to itself through a pointer, or that is used from several places,
+//! maps to a single gccjit struct.
 extern crate proc_macro;
 
-use synstructure::Structure;
-use syn::export::TokenStream;
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, Meta, NestedMeta};
 
+/// Integer representations that have a `Typeable` implementation.
+const INT_REPRS: &[&str] = &[
+    "i8", "u8", "i16", "u16", "i32", "u32", "i64", "u64", "i128", "u128", "isize", "usize",
+];
 
 #[proc_macro_derive(Typeable)]
-pub fn derive_typeable(s: TokenStream) -> proc_macro::TokenStream {
-    let derive_input: syn::DeriveInput = s.into();
-    let s = Structure::new(&derive_input);
-    let name = s.ast().ident.to_string();
-    let body = s.each(|bi| quote!{ctx.new_field(None,#bi::get_type(),stringify!(#bi)),});
-    let implementation = s.bound_impl(quote!(gccjit_rs::ty::Typeable),quote! {
-        fn get_type<'a,'ctx>(ctx: &'a gccjit_rs::ctx::Context<'ctx>) -> gccjit_rs::ty::Type<'a> {
-            let fields = vec![#body];
-            ctx.new_struct_type(None,&#name,&fields).as_type()
-        }
-    });
-
-    quote!(#implementation)
+pub fn derive_typeable(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
 }
-*/
-//decl_derive!([Typeable] => derive_typeable);
-
-#[macro_use]
-extern crate synstructure;
-#[macro_use]
-extern crate quote;
-extern crate proc_macro2;
-
 
+fn expand(mut input: DeriveInput) -> Result<TokenStream, Error> {
+    let reprs = reprs(&input)?;
+    let name = input.ident.clone();
+    let name_str = name.to_string();
 
+    let body = match &input.data {
+        Data::Struct(data) => {
+            require_repr_c(&input, &reprs)?;
+            let fields = fields(&data.fields);
+            quote! {
+                ::gccjit_rs::ty::struct_type_for::<Self>(ctx, #name_str, |ctx| vec![#(#fields),*])
+                    .as_type()
+            }
+        }
+        Data::Union(data) => {
+            require_repr_c(&input, &reprs)?;
+            let fields = fields(&Fields::Named(data.fields.clone()));
+            quote! {
+                ::gccjit_rs::ty::union_type_for::<Self>(ctx, #name_str, |ctx| vec![#(#fields),*])
+            }
+        }
+        Data::Enum(data) => {
+            if let Some(variant) = data.variants.iter().find(|v| !v.fields.is_empty()) {
+                return Err(Error::new(
+                    variant.span(),
+                    "#[derive(Typeable)] only supports enums without fields",
+                ));
+            }
+            let int = reprs
+                .iter()
+                .find(|r| INT_REPRS.contains(&r.as_str()))
+                .ok_or_else(|| {
+                    Error::new(
+                        name.span(),
+                        format!(
+                            "#[derive(Typeable)] requires `{}` to have an integer representation, \
+                             e.g. #[repr(u8)] or #[repr(i32)]",
+                            name_str
+                        ),
+                    )
+                })?;
+            let int = syn::Ident::new(int, Span::call_site());
+            quote! { <#int as ::gccjit_rs::ty::Typeable>::get_type(ctx) }
+        }
+    };
 
-fn derive_typeable(s: synstructure::Structure) -> proc_macro2::TokenStream {
-    let name = s.ast().ident.to_string();
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::gccjit_rs::ty::Typeable));
+        param.bounds.push(parse_quote!('static));
+    }
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
-    let body = s.each(|bi| {let fname = bi.ast().ident.clone().unwrap(); quote!{ctx.new_field(None,#bi::get_type(),&#fname),}});
-    s.bound_impl(quote!(gccjit_rs::ty::Typeable),quote! {
-        fn get_type<'a,'ctx>(ctx: &'a gccjit_rs::ctx::Context<'ctx>) -> gccjit_rs::ty::Type<'a> {
-            let fields = vec![#body];
-            ctx.new_struct_type(None,&#name,&fields).as_type()
+    Ok(quote! {
+        impl #impl_generics ::gccjit_rs::ty::Typeable for #name #ty_generics #where_clause {
+            fn get_type(ctx: &::gccjit_rs::ctx::Context) -> ::gccjit_rs::ty::Type {
+                #body
+            }
         }
     })
 }
 
-decl_derive!([Typeable] => derive_typeable);
\ No newline at end of file
+/// Collects the contents of every `#[repr(...)]` attribute on the item.
+fn reprs(input: &DeriveInput) -> Result<Vec<String>, Error> {
+    let mut reprs = vec![];
+    for attr in input.attrs.iter().filter(|a| a.path.is_ident("repr")) {
+        if let Meta::List(list) = attr.parse_meta()? {
+            for nested in list.nested.iter() {
+                match nested {
+                    NestedMeta::Meta(Meta::Path(path)) => {
+                        if let Some(ident) = path.get_ident() {
+                            reprs.push(ident.to_string());
+                        }
+                    }
+                    NestedMeta::Meta(meta) => {
+                        if let Some(ident) = meta.path().get_ident() {
+                            reprs.push(ident.to_string());
+                        }
+                    }
+                    NestedMeta::Lit(_) => {}
+                }
+            }
+        }
+    }
+    Ok(reprs)
+}
+
+fn require_repr_c(input: &DeriveInput, reprs: &[String]) -> Result<(), Error> {
+    if !reprs.iter().any(|r| r == "C") {
+        return Err(Error::new(
+            input.ident.span(),
+            format!(
+                "#[derive(Typeable)] requires `{}` to be #[repr(C)] so that its layout \
+                 matches the gccjit type",
+                input.ident
+            ),
+        ));
+    }
+    if let Some(r) = reprs.iter().find(|r| *r == "packed" || *r == "align") {
+        return Err(Error::new(
+            input.ident.span(),
+            format!("#[derive(Typeable)] does not support #[repr({})]", r),
+        ));
+    }
+    Ok(())
+}
+
+/// Builds a `ctx.new_field(...)` expression for every field, in declaration
+/// order. Tuple struct fields are named `_0`, `_1`, and so on.
+fn fields(fields: &Fields) -> Vec<TokenStream> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let name = match &field.ident {
+                Some(ident) => ident.to_string(),
+                None => format!("_{}", i),
+            };
+            let ty = &field.ty;
+            quote! {
+                ctx.new_field(None, <#ty as ::gccjit_rs::ty::Typeable>::get_type(ctx), #name)
+            }
+        })
+        .collect()
+}
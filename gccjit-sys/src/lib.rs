@@ -1196,6 +1196,16 @@ pub struct gcc_jit_param {
 pub struct gcc_jit_case {
     _unused: [u8; 0],
 }
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct gcc_jit_function_type {
+    _unused: [u8; 0],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct gcc_jit_vector_type {
+    _unused: [u8; 0],
+}
 extern "C" {
     pub fn gcc_jit_context_acquire() -> *mut gcc_jit_context;
 }
@@ -1363,6 +1373,16 @@ pub const gcc_jit_types_GCC_JIT_TYPE_FILE_PTR: gcc_jit_types = 19;
 pub const gcc_jit_types_GCC_JIT_TYPE_COMPLEX_FLOAT: gcc_jit_types = 20;
 pub const gcc_jit_types_GCC_JIT_TYPE_COMPLEX_DOUBLE: gcc_jit_types = 21;
 pub const gcc_jit_types_GCC_JIT_TYPE_COMPLEX_LONG_DOUBLE: gcc_jit_types = 22;
+pub const gcc_jit_types_GCC_JIT_TYPE_UINT8_T: gcc_jit_types = 23;
+pub const gcc_jit_types_GCC_JIT_TYPE_UINT16_T: gcc_jit_types = 24;
+pub const gcc_jit_types_GCC_JIT_TYPE_UINT32_T: gcc_jit_types = 25;
+pub const gcc_jit_types_GCC_JIT_TYPE_UINT64_T: gcc_jit_types = 26;
+pub const gcc_jit_types_GCC_JIT_TYPE_UINT128_T: gcc_jit_types = 27;
+pub const gcc_jit_types_GCC_JIT_TYPE_INT8_T: gcc_jit_types = 28;
+pub const gcc_jit_types_GCC_JIT_TYPE_INT16_T: gcc_jit_types = 29;
+pub const gcc_jit_types_GCC_JIT_TYPE_INT32_T: gcc_jit_types = 30;
+pub const gcc_jit_types_GCC_JIT_TYPE_INT64_T: gcc_jit_types = 31;
+pub const gcc_jit_types_GCC_JIT_TYPE_INT128_T: gcc_jit_types = 32;
 pub type gcc_jit_types = u32;
 extern "C" {
     pub fn gcc_jit_context_get_type(
@@ -1402,6 +1422,15 @@ extern "C" {
         name: *const ::std::os::raw::c_char,
     ) -> *mut gcc_jit_field;
 }
+extern "C" {
+    pub fn gcc_jit_context_new_bitfield(
+        ctxt: *mut gcc_jit_context,
+        loc: *mut gcc_jit_location,
+        type_: *mut gcc_jit_type,
+        width: ::std::os::raw::c_int,
+        name: *const ::std::os::raw::c_char,
+    ) -> *mut gcc_jit_field;
+}
 extern "C" {
     pub fn gcc_jit_field_as_object(field: *mut gcc_jit_field) -> *mut gcc_jit_object;
 }
@@ -1879,6 +1908,189 @@ extern "C" {
         elements: *mut *mut gcc_jit_rvalue,
     ) -> *mut gcc_jit_rvalue;
 }
+extern "C" {
+    pub fn gcc_jit_context_new_struct_constructor(
+        ctxt: *mut gcc_jit_context,
+        loc: *mut gcc_jit_location,
+        type_: *mut gcc_jit_type,
+        num_values: usize,
+        fields: *mut *mut gcc_jit_field,
+        values: *mut *mut gcc_jit_rvalue,
+    ) -> *mut gcc_jit_rvalue;
+}
+extern "C" {
+    pub fn gcc_jit_context_new_array_constructor(
+        ctxt: *mut gcc_jit_context,
+        loc: *mut gcc_jit_location,
+        type_: *mut gcc_jit_type,
+        num_values: usize,
+        values: *mut *mut gcc_jit_rvalue,
+    ) -> *mut gcc_jit_rvalue;
+}
+extern "C" {
+    pub fn gcc_jit_context_new_union_constructor(
+        ctxt: *mut gcc_jit_context,
+        loc: *mut gcc_jit_location,
+        type_: *mut gcc_jit_type,
+        field: *mut gcc_jit_field,
+        value: *mut gcc_jit_rvalue,
+    ) -> *mut gcc_jit_rvalue;
+}
+extern "C" {
+    pub fn gcc_jit_context_new_sizeof(
+        ctxt: *mut gcc_jit_context,
+        type_: *mut gcc_jit_type,
+    ) -> *mut gcc_jit_rvalue;
+}
+extern "C" {
+    pub fn gcc_jit_context_new_alignof(
+        ctxt: *mut gcc_jit_context,
+        type_: *mut gcc_jit_type,
+    ) -> *mut gcc_jit_rvalue;
+}
+extern "C" {
+    pub fn gcc_jit_context_new_bitcast(
+        ctxt: *mut gcc_jit_context,
+        loc: *mut gcc_jit_location,
+        rvalue: *mut gcc_jit_rvalue,
+        type_: *mut gcc_jit_type,
+    ) -> *mut gcc_jit_rvalue;
+}
+extern "C" {
+    pub fn gcc_jit_global_set_initializer(
+        global: *mut gcc_jit_lvalue,
+        blob: *const ::std::os::raw::c_void,
+        num_bytes: usize,
+    ) -> *mut gcc_jit_lvalue;
+}
+extern "C" {
+    pub fn gcc_jit_global_set_initializer_rvalue(
+        global: *mut gcc_jit_lvalue,
+        init_value: *mut gcc_jit_rvalue,
+    ) -> *mut gcc_jit_lvalue;
+}
+pub const gcc_jit_tls_model_GCC_JIT_TLS_MODEL_NONE: gcc_jit_tls_model = 0;
+pub const gcc_jit_tls_model_GCC_JIT_TLS_MODEL_GLOBAL_DYNAMIC: gcc_jit_tls_model = 1;
+pub const gcc_jit_tls_model_GCC_JIT_TLS_MODEL_LOCAL_DYNAMIC: gcc_jit_tls_model = 2;
+pub const gcc_jit_tls_model_GCC_JIT_TLS_MODEL_INITIAL_EXEC: gcc_jit_tls_model = 3;
+pub const gcc_jit_tls_model_GCC_JIT_TLS_MODEL_LOCAL_EXEC: gcc_jit_tls_model = 4;
+pub type gcc_jit_tls_model = u32;
+extern "C" {
+    pub fn gcc_jit_lvalue_set_tls_model(lvalue: *mut gcc_jit_lvalue, model: gcc_jit_tls_model);
+}
+extern "C" {
+    pub fn gcc_jit_lvalue_set_link_section(
+        lvalue: *mut gcc_jit_lvalue,
+        section_name: *const ::std::os::raw::c_char,
+    );
+}
+extern "C" {
+    pub fn gcc_jit_lvalue_set_register_name(
+        lvalue: *mut gcc_jit_lvalue,
+        reg_name: *const ::std::os::raw::c_char,
+    );
+}
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_ALIAS: gcc_jit_fn_attribute = 0;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_ALWAYS_INLINE: gcc_jit_fn_attribute = 1;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_INLINE: gcc_jit_fn_attribute = 2;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_NOINLINE: gcc_jit_fn_attribute = 3;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_TARGET: gcc_jit_fn_attribute = 4;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_USED: gcc_jit_fn_attribute = 5;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_VISIBILITY: gcc_jit_fn_attribute = 6;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_COLD: gcc_jit_fn_attribute = 7;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_RETURNS_TWICE: gcc_jit_fn_attribute = 8;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_PURE: gcc_jit_fn_attribute = 9;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_CONST: gcc_jit_fn_attribute = 10;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_WEAK: gcc_jit_fn_attribute = 11;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_NONNULL: gcc_jit_fn_attribute = 12;
+pub const gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_MAX: gcc_jit_fn_attribute = 13;
+pub type gcc_jit_fn_attribute = u32;
+extern "C" {
+    pub fn gcc_jit_function_add_string_attribute(
+        func: *mut gcc_jit_function,
+        attribute: gcc_jit_fn_attribute,
+        value: *const ::std::os::raw::c_char,
+    );
+}
+pub const gcc_jit_variable_attribute_GCC_JIT_VARIABLE_ATTRIBUTE_VISIBILITY:
+    gcc_jit_variable_attribute = 0;
+pub const gcc_jit_variable_attribute_GCC_JIT_VARIABLE_ATTRIBUTE_MAX: gcc_jit_variable_attribute =
+    1;
+pub type gcc_jit_variable_attribute = u32;
+extern "C" {
+    pub fn gcc_jit_lvalue_add_string_attribute(
+        variable: *mut gcc_jit_lvalue,
+        attribute: gcc_jit_variable_attribute,
+        value: *const ::std::os::raw::c_char,
+    );
+}
+extern "C" {
+    pub fn gcc_jit_function_get_return_type(func: *mut gcc_jit_function) -> *mut gcc_jit_type;
+}
+extern "C" {
+    pub fn gcc_jit_function_get_param_count(func: *mut gcc_jit_function) -> usize;
+}
+extern "C" {
+    pub fn gcc_jit_type_dyncast_array(type_: *mut gcc_jit_type) -> *mut gcc_jit_type;
+}
+extern "C" {
+    pub fn gcc_jit_type_is_bool(type_: *mut gcc_jit_type) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn gcc_jit_type_dyncast_function_ptr_type(
+        type_: *mut gcc_jit_type,
+    ) -> *mut gcc_jit_function_type;
+}
+extern "C" {
+    pub fn gcc_jit_function_type_get_return_type(
+        function_type: *mut gcc_jit_function_type,
+    ) -> *mut gcc_jit_type;
+}
+extern "C" {
+    pub fn gcc_jit_function_type_get_param_count(function_type: *mut gcc_jit_function_type)
+        -> usize;
+}
+extern "C" {
+    pub fn gcc_jit_function_type_get_param_type(
+        function_type: *mut gcc_jit_function_type,
+        index: usize,
+    ) -> *mut gcc_jit_type;
+}
+extern "C" {
+    pub fn gcc_jit_type_is_integral(type_: *mut gcc_jit_type) -> ::std::os::raw::c_int;
+}
+extern "C" {
+    pub fn gcc_jit_type_is_pointer(type_: *mut gcc_jit_type) -> *mut gcc_jit_type;
+}
+extern "C" {
+    pub fn gcc_jit_type_dyncast_vector(type_: *mut gcc_jit_type) -> *mut gcc_jit_vector_type;
+}
+extern "C" {
+    pub fn gcc_jit_type_is_struct(type_: *mut gcc_jit_type) -> *mut gcc_jit_struct;
+}
+extern "C" {
+    pub fn gcc_jit_vector_type_get_num_units(vector_type: *mut gcc_jit_vector_type) -> usize;
+}
+extern "C" {
+    pub fn gcc_jit_vector_type_get_element_type(
+        vector_type: *mut gcc_jit_vector_type,
+    ) -> *mut gcc_jit_type;
+}
+extern "C" {
+    pub fn gcc_jit_type_unqualified(type_: *mut gcc_jit_type) -> *mut gcc_jit_type;
+}
+extern "C" {
+    pub fn gcc_jit_struct_get_field(
+        struct_type: *mut gcc_jit_struct,
+        index: usize,
+    ) -> *mut gcc_jit_field;
+}
+extern "C" {
+    pub fn gcc_jit_struct_get_field_count(struct_type: *mut gcc_jit_struct) -> usize;
+}
+extern "C" {
+    pub fn gcc_jit_type_get_size(type_: *mut gcc_jit_type) -> __ssize_t;
+}
 pub type __builtin_va_list = [__va_list_tag; 1usize];
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
@@ -0,0 +1,341 @@
+//! Integer arithmetic with Rust's semantics. The operators on RValue build
+//! C expressions, where signed overflow and shifting by the width of the
+//! type or more are undefined, so GCC may assume they never happen.
+//! RustArith builds the operations a Rust-like language defines instead:
+//! wrapping arithmetic, saturating arithmetic, masked shifts and division
+//! that traps rather than invoking undefined behavior.
+//!
+//! Both operands must have the same integer type, except for the shift
+//! amount, which may be any integer. Operands can be evaluated more than
+//! once, so ones with side effects should be stored in a local first.
+use crate::block::{self, BinaryOp, Block, ComparisonOp, UnaryOp};
+use crate::ctx::Context;
+use crate::layout;
+use crate::location::Location;
+use crate::rvalue::{RValue, ToRValue};
+use crate::ty::Type;
+
+/// Builds integer operations that behave exactly like Rust's.
+#[derive(Copy, Clone)]
+pub struct RustArith {
+    ctx: Context,
+}
+
+impl RustArith {
+    pub fn new(ctx: &Context) -> RustArith {
+        RustArith { ctx: *ctx }
+    }
+
+    /// `a.wrapping_add(b)`.
+    pub fn wrapping_add<L: ToRValue, R: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        a: L,
+        b: R,
+    ) -> RValue {
+        self.wrapping(loc, BinaryOp::Plus, a.to_rvalue(), b.to_rvalue())
+    }
+
+    /// `a.wrapping_sub(b)`.
+    pub fn wrapping_sub<L: ToRValue, R: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        a: L,
+        b: R,
+    ) -> RValue {
+        self.wrapping(loc, BinaryOp::Minus, a.to_rvalue(), b.to_rvalue())
+    }
+
+    /// `a.wrapping_mul(b)`.
+    pub fn wrapping_mul<L: ToRValue, R: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        a: L,
+        b: R,
+    ) -> RValue {
+        self.wrapping(loc, BinaryOp::Mult, a.to_rvalue(), b.to_rvalue())
+    }
+
+    /// `a.wrapping_neg()`.
+    pub fn wrapping_neg<T: ToRValue>(&self, loc: Option<Location>, a: T) -> RValue {
+        let a = a.to_rvalue();
+        let ty = self.int_type(a.get_type());
+        let zero = self.ctx.new_rvalue_zero(ty);
+        self.wrapping(loc, BinaryOp::Minus, zero, a)
+    }
+
+    /// `a.wrapping_shl(b)`: the shift amount is taken modulo the width of a.
+    pub fn wrapping_shl<L: ToRValue, R: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        a: L,
+        b: R,
+    ) -> RValue {
+        self.shift(loc, BinaryOp::LShift, a.to_rvalue(), b.to_rvalue())
+    }
+
+    /// `a.wrapping_shr(b)`: the shift amount is taken modulo the width of a.
+    /// Signed values are shifted arithmetically.
+    pub fn wrapping_shr<L: ToRValue, R: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        a: L,
+        b: R,
+    ) -> RValue {
+        self.shift(loc, BinaryOp::RShift, a.to_rvalue(), b.to_rvalue())
+    }
+
+    /// `a.saturating_add(b)`, added to the block.
+    pub fn saturating_add<L: ToRValue, R: ToRValue>(
+        &self,
+        block: Block,
+        loc: Option<Location>,
+        a: L,
+        b: R,
+    ) -> RValue {
+        self.saturating(block, loc, BinaryOp::Plus, a.to_rvalue(), b.to_rvalue())
+    }
+
+    /// `a.saturating_sub(b)`, added to the block.
+    pub fn saturating_sub<L: ToRValue, R: ToRValue>(
+        &self,
+        block: Block,
+        loc: Option<Location>,
+        a: L,
+        b: R,
+    ) -> RValue {
+        self.saturating(block, loc, BinaryOp::Minus, a.to_rvalue(), b.to_rvalue())
+    }
+
+    /// `a.saturating_mul(b)`, added to the block.
+    pub fn saturating_mul<L: ToRValue, R: ToRValue>(
+        &self,
+        block: Block,
+        loc: Option<Location>,
+        a: L,
+        b: R,
+    ) -> RValue {
+        self.saturating(block, loc, BinaryOp::Mult, a.to_rvalue(), b.to_rvalue())
+    }
+
+    /// `a / b`, trapping like Rust's panic when b is zero or when the
+    /// division overflows, i.e. `MIN / -1`. The block ends with the check,
+    /// and the quotient is returned with the block to continue in.
+    pub fn div<L: ToRValue, R: ToRValue>(
+        &self,
+        block: Block,
+        loc: Option<Location>,
+        a: L,
+        b: R,
+    ) -> (RValue, Block) {
+        self.division(block, loc, BinaryOp::Divide, a.to_rvalue(), b.to_rvalue())
+    }
+
+    /// `a % b`, trapping in the same cases as div.
+    pub fn rem<L: ToRValue, R: ToRValue>(
+        &self,
+        block: Block,
+        loc: Option<Location>,
+        a: L,
+        b: R,
+    ) -> (RValue, Block) {
+        self.division(block, loc, BinaryOp::Modulo, a.to_rvalue(), b.to_rvalue())
+    }
+
+    /// Checks that a type is an integer other than bool.
+    fn int_type(&self, ty: Type) -> Type {
+        if !ty.is_integral() || ty == self.ctx.new_type::<bool>() {
+            panic!("Rust arithmetic needs integers, not {:?}", ty);
+        }
+        ty
+    }
+
+    fn operand_type(&self, a: RValue, b: RValue) -> Type {
+        let ty = self.int_type(a.get_type());
+        if b.get_type() != ty {
+            panic!(
+                "Rust arithmetic needs operands of the same type, not {:?} and {:?}",
+                ty,
+                b.get_type()
+            );
+        }
+        ty
+    }
+
+    /// The unsigned type as wide as `ty`.
+    fn unsigned(&self, ty: Type) -> Type {
+        match layout::size_of(ty) {
+            1 => self.ctx.new_type::<u8>(),
+            2 => self.ctx.new_type::<u16>(),
+            4 => self.ctx.new_type::<u32>(),
+            8 => self.ctx.new_type::<u64>(),
+            16 => self.ctx.new_type::<u128>(),
+            size => panic!("no unsigned type of {} bytes", size),
+        }
+    }
+
+    fn bits(&self, ty: Type) -> u32 {
+        8 * layout::size_of(ty) as u32
+    }
+
+    fn min(&self, ty: Type) -> RValue {
+        if ty.is_signed() {
+            let min = -1i128 << (self.bits(ty) - 1);
+            self.ctx.new_rvalue_from_i128(ty, min)
+        } else {
+            self.ctx.new_rvalue_zero(ty)
+        }
+    }
+
+    fn max(&self, ty: Type) -> RValue {
+        let unsigned_max = u128::MAX >> (128 - self.bits(ty));
+        let max = if ty.is_signed() {
+            unsigned_max >> 1
+        } else {
+            unsigned_max
+        };
+        self.ctx.new_rvalue_from_u128(ty, max)
+    }
+
+    /// Does the operation on the unsigned type of the same width, where
+    /// overflow is defined to wrap, and converts back.
+    fn wrapping(&self, loc: Option<Location>, op: BinaryOp, a: RValue, b: RValue) -> RValue {
+        let ty = self.operand_type(a, b);
+        if !ty.is_signed() {
+            return self.ctx.new_binary_op(loc, op, ty, a, b);
+        }
+        let unsigned = self.unsigned(ty);
+        let a = self.ctx.new_cast(loc, a, unsigned);
+        let b = self.ctx.new_cast(loc, b, unsigned);
+        let result = self.ctx.new_binary_op(loc, op, unsigned, a, b);
+        self.ctx.new_cast(loc, result, ty)
+    }
+
+    fn shift(&self, loc: Option<Location>, op: BinaryOp, a: RValue, b: RValue) -> RValue {
+        let ty = self.int_type(a.get_type());
+        let amount_ty = self.int_type(b.get_type());
+        let mask = self
+            .ctx
+            .new_rvalue_from_u128(amount_ty, self.bits(ty) as u128 - 1);
+        let amount = self
+            .ctx
+            .new_binary_op(loc, BinaryOp::BitwiseAnd, amount_ty, b, mask);
+        match op {
+            BinaryOp::LShift if ty.is_signed() => {
+                let unsigned = self.unsigned(ty);
+                let a = self.ctx.new_cast(loc, a, unsigned);
+                let amount = self.ctx.new_cast(loc, amount, unsigned);
+                let result = self.ctx.new_binary_op(loc, op, unsigned, a, amount);
+                self.ctx.new_cast(loc, result, ty)
+            }
+            _ => {
+                let amount = self.ctx.new_cast(loc, amount, ty);
+                self.ctx.new_binary_op(loc, op, ty, a, amount)
+            }
+        }
+    }
+
+    /// Picks `if_true` or `if_false` without branching, by masking in the
+    /// unsigned type.
+    fn select(
+        &self,
+        loc: Option<Location>,
+        cond: RValue,
+        if_true: RValue,
+        if_false: RValue,
+    ) -> RValue {
+        let ty = if_true.get_type();
+        let unsigned = self.unsigned(ty);
+        let cond = self.ctx.new_cast(loc, cond, unsigned);
+        let mask = self.ctx.new_unary_op(loc, UnaryOp::Minus, unsigned, cond);
+        let if_true = self.ctx.new_cast(loc, if_true, unsigned);
+        let if_false = self.ctx.new_cast(loc, if_false, unsigned);
+        let diff = self
+            .ctx
+            .new_binary_op(loc, BinaryOp::BitwiseXor, unsigned, if_true, if_false);
+        let diff = self
+            .ctx
+            .new_binary_op(loc, BinaryOp::BitwiseAnd, unsigned, diff, mask);
+        let result = self
+            .ctx
+            .new_binary_op(loc, BinaryOp::BitwiseXor, unsigned, if_false, diff);
+        self.ctx.new_cast(loc, result, ty)
+    }
+
+    fn is_negative(&self, loc: Option<Location>, value: RValue) -> RValue {
+        let zero = self.ctx.new_rvalue_zero(value.get_type());
+        self.ctx
+            .new_comparison(loc, ComparisonOp::LessThan, value, zero)
+    }
+
+    fn saturating(
+        &self,
+        block: Block,
+        loc: Option<Location>,
+        op: BinaryOp,
+        a: RValue,
+        b: RValue,
+    ) -> RValue {
+        let ty = self.operand_type(a, b);
+        let (min, max) = (self.min(ty), self.max(ty));
+        // The bound an overflow runs into: unsigned addition and
+        // multiplication overflow upwards and subtraction downwards. Signed
+        // operations overflow towards the sign of the exact result.
+        let limit = if !ty.is_signed() {
+            match op {
+                BinaryOp::Minus => min,
+                _ => max,
+            }
+        } else {
+            let below = match op {
+                BinaryOp::Plus => self.is_negative(loc, b),
+                BinaryOp::Minus => self.ctx.new_comparison(
+                    loc,
+                    ComparisonOp::GreaterThan,
+                    b,
+                    self.ctx.new_rvalue_zero(ty),
+                ),
+                _ => self.ctx.new_comparison(
+                    loc,
+                    ComparisonOp::NotEquals,
+                    self.is_negative(loc, a),
+                    self.is_negative(loc, b),
+                ),
+            };
+            self.select(loc, below, min, max)
+        };
+        let (result, overflowed) = self.ctx.new_overflowing_op(block, loc, op, a, b, ty);
+        self.select(loc, overflowed, limit, result)
+    }
+
+    fn division(
+        &self,
+        block: Block,
+        loc: Option<Location>,
+        op: BinaryOp,
+        a: RValue,
+        b: RValue,
+    ) -> (RValue, Block) {
+        let ty = self.operand_type(a, b);
+        let boolean = self.ctx.new_type::<bool>();
+        let zero = self.ctx.new_rvalue_zero(ty);
+        let mut fails = self.ctx.new_comparison(loc, ComparisonOp::Equals, b, zero);
+        if ty.is_signed() {
+            let minus_one = self.ctx.new_rvalue_from_i128(ty, -1);
+            let is_min = self
+                .ctx
+                .new_comparison(loc, ComparisonOp::Equals, a, self.min(ty));
+            let is_minus_one = self
+                .ctx
+                .new_comparison(loc, ComparisonOp::Equals, b, minus_one);
+            let overflows =
+                self.ctx
+                    .new_binary_op(loc, BinaryOp::LogicalAnd, boolean, is_min, is_minus_one);
+            fails = self
+                .ctx
+                .new_binary_op(loc, BinaryOp::LogicalOr, boolean, fails, overflows);
+        }
+        let ok = block::trap_if(block, loc, fails, "division");
+        (self.ctx.new_binary_op(loc, op, ty, a, b), ok)
+    }
+}
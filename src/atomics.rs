@@ -0,0 +1,330 @@
+//! Atomic operations on integer and pointer LValues, lowered to GCC's
+//! `__atomic_*` builtins. Orderings follow Rust's std::sync::atomic, and
+//! combinations Rust rejects, such as a Release load, panic here too.
+//!
+//! Like a call made with Context::new_call, an operation that returns an
+//! RValue happens where that RValue is evaluated, e.g. by `Block::add_eval`
+//! or by assigning it somewhere.
+use std::sync::atomic::Ordering;
+
+use crate::block::Block;
+use crate::ctx::Context;
+use crate::function::Function;
+use crate::layout;
+use crate::location::Location;
+use crate::lvalue::{LValue, ToLValue};
+use crate::object::ToObject;
+use crate::rvalue::{RValue, ToRValue};
+use crate::ty::Type;
+
+/// An LValue of integer or pointer type that is accessed atomically.
+#[derive(Copy, Clone, Debug)]
+pub struct Atomic {
+    lvalue: LValue,
+    ty: Type,
+    size: usize,
+    is_pointer: bool,
+}
+
+impl Atomic {
+    /// Wraps an LValue. Panics unless it is an integer or a pointer of 1, 2,
+    /// 4, 8 or 16 bytes. Qualifiers are allowed, so a volatile global can be
+    /// accessed atomically; values are loaded and stored unqualified.
+    pub fn new<L: ToLValue>(lvalue: L) -> Atomic {
+        let lvalue = lvalue.to_lvalue();
+        let ty = lvalue.to_rvalue().get_type().unqualified();
+        let is_pointer = ty.is_pointer();
+        if !is_pointer && !ty.is_integral() {
+            panic!(
+                "atomic operations need an integer or a pointer, not {:?}",
+                ty
+            );
+        }
+        let size = layout::size_of(ty);
+        if ![1, 2, 4, 8, 16].contains(&size) {
+            panic!("no atomic operations for {:?}, which is {} bytes", ty, size);
+        }
+        Atomic {
+            lvalue,
+            ty,
+            size,
+            is_pointer,
+        }
+    }
+
+    /// Gets the LValue that is accessed atomically.
+    pub fn lvalue(&self) -> LValue {
+        self.lvalue
+    }
+
+    /// Loads the value. Panics for Release and AcqRel.
+    pub fn load(&self, loc: Option<Location>, order: Ordering) -> RValue {
+        if let Ordering::Release | Ordering::AcqRel = order {
+            panic!("there is no {:?} atomic load", order);
+        }
+        let func = self.builtin("load");
+        let call = self.call(loc, func, &[self.ptr_arg(loc, func), self.order(order)]);
+        self.result_value(loc, call)
+    }
+
+    /// Adds a store of `value` to the block. Panics for Acquire and AcqRel.
+    pub fn store<T: ToRValue>(
+        &self,
+        block: Block,
+        loc: Option<Location>,
+        value: T,
+        order: Ordering,
+    ) {
+        if let Ordering::Acquire | Ordering::AcqRel = order {
+            panic!("there is no {:?} atomic store", order);
+        }
+        let func = self.builtin("store");
+        let value = self.value_arg(loc, func, value);
+        let call = self.call(
+            loc,
+            func,
+            &[self.ptr_arg(loc, func), value, self.order(order)],
+        );
+        block.add_eval(loc, call);
+    }
+
+    /// Stores `value` and returns the previous value.
+    pub fn exchange<T: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        value: T,
+        order: Ordering,
+    ) -> RValue {
+        let func = self.builtin("exchange");
+        let value = self.value_arg(loc, func, value);
+        let call = self.call(
+            loc,
+            func,
+            &[self.ptr_arg(loc, func), value, self.order(order)],
+        );
+        self.result_value(loc, call)
+    }
+
+    /// Stores `desired` if the current value equals `expected`, and returns
+    /// a bool telling whether it did. `expected` is usually a local holding
+    /// the value that was last seen; when the exchange fails it is set to
+    /// the current value, ready for another attempt.
+    ///
+    /// Panics if `failure` is Release or AcqRel, as in Rust.
+    pub fn compare_exchange<E: ToLValue, T: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        expected: E,
+        desired: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> RValue {
+        self.compare_exchange_impl(loc, expected, desired, false, success, failure)
+    }
+
+    /// Like compare_exchange, but may fail even when the values are equal,
+    /// which gives better code in a retry loop on some targets.
+    pub fn compare_exchange_weak<E: ToLValue, T: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        expected: E,
+        desired: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> RValue {
+        self.compare_exchange_impl(loc, expected, desired, true, success, failure)
+    }
+
+    fn compare_exchange_impl<E: ToLValue, T: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        expected: E,
+        desired: T,
+        weak: bool,
+        success: Ordering,
+        failure: Ordering,
+    ) -> RValue {
+        if let Ordering::Release | Ordering::AcqRel = failure {
+            panic!(
+                "there is no {:?} failure ordering for compare_exchange",
+                failure
+            );
+        }
+        let expected = expected.to_lvalue();
+        self.check_type(expected.to_rvalue());
+        let ctx = self.context();
+        let func = self.builtin("compare_exchange");
+        let expected_ty = func.get_param(1).to_rvalue().get_type();
+        let expected = ctx.new_cast(loc, expected.get_address(loc), expected_ty);
+        let desired = self.value_arg(loc, func, desired);
+        let args = [
+            self.ptr_arg(loc, func),
+            expected,
+            desired,
+            ctx.new_const(weak),
+            self.order(success),
+            self.order(failure),
+        ];
+        self.call(loc, func, &args)
+    }
+
+    /// Adds `value` and returns the previous value. Only for integers.
+    pub fn fetch_add<T: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        value: T,
+        order: Ordering,
+    ) -> RValue {
+        self.fetch("fetch_add", loc, value, order)
+    }
+
+    /// Subtracts `value` and returns the previous value. Only for integers.
+    pub fn fetch_sub<T: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        value: T,
+        order: Ordering,
+    ) -> RValue {
+        self.fetch("fetch_sub", loc, value, order)
+    }
+
+    /// Ands in `value` and returns the previous value. Only for integers.
+    pub fn fetch_and<T: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        value: T,
+        order: Ordering,
+    ) -> RValue {
+        self.fetch("fetch_and", loc, value, order)
+    }
+
+    /// Ors in `value` and returns the previous value. Only for integers.
+    pub fn fetch_or<T: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        value: T,
+        order: Ordering,
+    ) -> RValue {
+        self.fetch("fetch_or", loc, value, order)
+    }
+
+    /// Xors in `value` and returns the previous value. Only for integers.
+    pub fn fetch_xor<T: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        value: T,
+        order: Ordering,
+    ) -> RValue {
+        self.fetch("fetch_xor", loc, value, order)
+    }
+
+    fn fetch<T: ToRValue>(
+        &self,
+        name: &str,
+        loc: Option<Location>,
+        value: T,
+        order: Ordering,
+    ) -> RValue {
+        if self.is_pointer {
+            panic!("{} needs an integer, not the pointer {:?}", name, self.ty);
+        }
+        let func = self.builtin(name);
+        let value = self.value_arg(loc, func, value);
+        let call = self.call(
+            loc,
+            func,
+            &[self.ptr_arg(loc, func), value, self.order(order)],
+        );
+        self.result_value(loc, call)
+    }
+
+    fn context(&self) -> Context {
+        self.lvalue.to_object().get_context()
+    }
+
+    /// Gets the size-specific builtin, e.g. __atomic_load_4.
+    fn builtin(&self, name: &str) -> Function {
+        let name = format!("__atomic_{}_{}", name, self.size);
+        self.context().get_builtin_function(name)
+    }
+
+    fn call(&self, loc: Option<Location>, func: Function, args: &[RValue]) -> RValue {
+        self.context().new_call(loc, func, args)
+    }
+
+    fn order(&self, order: Ordering) -> RValue {
+        let ctx = self.context();
+        ctx.new_rvalue_from_int(ctx.new_type::<i32>(), memory_order(order))
+    }
+
+    fn ptr_arg(&self, loc: Option<Location>, func: Function) -> RValue {
+        let ty = func.get_param(0).to_rvalue().get_type();
+        self.context()
+            .new_cast(loc, self.lvalue.get_address(loc), ty)
+    }
+
+    fn check_type(&self, value: RValue) {
+        if value.get_type().unqualified() != self.ty {
+            panic!(
+                "atomic operation on {:?} given {:?} of type {:?}",
+                self.ty,
+                value,
+                value.get_type()
+            );
+        }
+    }
+
+    /// Converts a value of the atomic's type to the builtin's integer type.
+    fn value_arg<T: ToRValue>(&self, loc: Option<Location>, func: Function, value: T) -> RValue {
+        let value = value.to_rvalue();
+        self.check_type(value);
+        let int_ty = func.get_param(1).to_rvalue().get_type();
+        if self.is_pointer {
+            self.context().new_bitcast(loc, value, int_ty)
+        } else {
+            self.context().new_cast(loc, value, int_ty)
+        }
+    }
+
+    /// Converts a result of the builtin back to the atomic's type.
+    fn result_value(&self, loc: Option<Location>, value: RValue) -> RValue {
+        if self.is_pointer {
+            self.context().new_bitcast(loc, value, self.ty)
+        } else {
+            self.context().new_cast(loc, value, self.ty)
+        }
+    }
+}
+
+/// Adds a memory fence to the block, like std::sync::atomic::fence.
+/// Panics for Relaxed.
+pub fn fence(block: Block, loc: Option<Location>, order: Ordering) {
+    add_fence(block, loc, "__atomic_thread_fence", order);
+}
+
+/// Adds a fence that only stops the compiler from reordering memory
+/// accesses, like std::sync::atomic::compiler_fence. Panics for Relaxed.
+pub fn compiler_fence(block: Block, loc: Option<Location>, order: Ordering) {
+    add_fence(block, loc, "__atomic_signal_fence", order);
+}
+
+fn add_fence(block: Block, loc: Option<Location>, name: &str, order: Ordering) {
+    if let Ordering::Relaxed = order {
+        panic!("there is no Relaxed fence");
+    }
+    let ctx = block.to_object().get_context();
+    let order = ctx.new_rvalue_from_int(ctx.new_type::<i32>(), memory_order(order));
+    let call = ctx.new_call(loc, ctx.get_builtin_function(name), &[order]);
+    block.add_eval(loc, call);
+}
+
+/// The __ATOMIC_* constant for an ordering.
+fn memory_order(order: Ordering) -> i32 {
+    match order {
+        Ordering::Relaxed => 0,
+        Ordering::Acquire => 2,
+        Ordering::Release => 3,
+        Ordering::AcqRel => 4,
+        _ => 5,
+    }
+}
@@ -1,4 +1,4 @@
-use crate::ctx::Context;
+use crate::ctx::{self, Context};
 use crate::function::{self, Function};
 use crate::location::{self, Location};
 use crate::lvalue::{self, ToLValue};
@@ -7,6 +7,7 @@ use crate::rvalue::{self, ToRValue};
 use gccjit_sys;
 use gccjit_sys::{gcc_jit_block_end_with_switch, gcc_jit_case, gcc_jit_case_as_object};
 use std::ffi::CString;
+use std::collections::HashSet;
 use std::fmt;
 use std::marker::PhantomData;
 use std::mem;
@@ -211,6 +212,21 @@ impl Block {
                 on_false.ptr,
             );
         }
+        self.record_end();
+    }
+
+    /// Like end_with_conditional, but the condition may be any scalar, which
+    /// is converted to bool the way C's `if` does.
+    pub fn end_with_promoted_conditional<T: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        cond: T,
+        on_true: Block,
+        on_false: Block,
+    ) {
+        let ctx = self.to_object().get_context();
+        let cond = ctx.new_truth_value(loc, cond);
+        self.end_with_conditional(loc, cond, on_true, on_false);
     }
 
     /// Terminates a block by unconditionally jumping to another block.
@@ -222,6 +238,7 @@ impl Block {
         unsafe {
             gccjit_sys::gcc_jit_block_end_with_jump(self.ptr, loc_ptr, target.ptr);
         }
+        self.record_end();
     }
 
     pub fn end_with_switch(
@@ -242,6 +259,7 @@ impl Block {
                 cases_.as_mut_ptr(),
             );
         }
+        self.record_end();
     }
     /// Terminates a block by returning from the containing function, setting
     /// the rvalue to be the return value of the function. This is equivalent
@@ -260,6 +278,7 @@ impl Block {
                 rvalue::get_ptr(&ret_rvalue),
             );
         }
+        self.record_end();
     }
 
     /// Terminates a block by returning from the containing function, returning
@@ -274,7 +293,50 @@ impl Block {
         unsafe {
             gccjit_sys::gcc_jit_block_end_with_void_return(self.ptr, loc_ptr);
         }
+        self.record_end();
     }
+
+    /// Returns true once the block has been ended by one of the end_with
+    /// methods.
+    pub fn is_terminated(&self) -> bool {
+        let ctx = self.to_object().get_context();
+        let ended = ctx::context_state::<Ended>(&ctx);
+        let ended = ended.borrow();
+        ended.0.contains(&(self.ptr as usize))
+    }
+
+    fn record_end(&self) {
+        let ctx = self.to_object().get_context();
+        ctx::context_state::<Ended>(&ctx)
+            .borrow_mut()
+            .0
+            .insert(self.ptr as usize);
+    }
+}
+
+/// The blocks of a context that have been ended.
+#[derive(Default)]
+struct Ended(HashSet<usize>);
+
+/// Ends the block with a check of `fails`, calling __builtin_trap if it is
+/// true, and returns the block to continue in when it is false. The new
+/// blocks are named after `what`.
+pub(crate) fn trap_if(
+    block: Block,
+    loc: Option<Location>,
+    fails: rvalue::RValue,
+    what: &str,
+) -> Block {
+    let ctx = block.to_object().get_context();
+    let func = block.get_function();
+    let trap = func.new_block(format!("{}_trap", what));
+    let ok = func.new_block(format!("{}_ok", what));
+    block.end_with_conditional(loc, fails, trap, ok);
+    let trap_fn = ctx.get_builtin_function("__builtin_trap");
+    trap.add_eval(loc, ctx.new_call(loc, trap_fn, &[]));
+    // __builtin_trap doesn't return, but the block still needs an end.
+    trap.end_with_jump(loc, ok);
+    ok
 }
 
 pub unsafe fn from_ptr(ptr: *mut gccjit_sys::gcc_jit_block) -> Block {
@@ -0,0 +1,351 @@
+//! Structured control flow on top of blocks. A FunctionBuilder keeps track
+//! of the block code is being added to, and builds ifs and loops out of
+//! closures that add their bodies, creating, wiring and terminating the
+//! blocks in between.
+//!
+//! gccjit rejects both unterminated and unreachable blocks, so a block is
+//! only created once something can jump to it: an `if` whose branches all
+//! return has no join block, and a `loop_` without a `break_` has no exit.
+use crate::block::{BinaryOp, Block, ComparisonOp};
+use crate::ctx::Context;
+use crate::function::Function;
+use crate::location::Location;
+use crate::object::ToObject;
+use crate::rvalue::{RValue, ToRValue};
+
+/// A block that is created the first time something jumps to it.
+struct Target {
+    name: String,
+    block: Option<Block>,
+}
+
+struct Loop {
+    continue_to: Target,
+    break_to: Target,
+}
+
+/// Builds the body of a function with ifs and loops instead of hand-wired
+/// blocks. Statements go into `block()`, e.g.
+/// `builder.block().add_assignment(None, x, y)`, and the block also becomes
+/// the context's current block, so compound assignment operators on LValues
+/// go there too. With several builders on one context, that is the block of
+/// whichever builder last called block() or changed blocks; the current
+/// block from before the builder is restored when it is dropped.
+///
+/// Ending block() by hand, e.g. with end_with_return, is allowed: the
+/// builder treats the code after it as unreachable, as after return_.
+///
+/// The last block is terminated when the builder is finished or dropped:
+/// a void function returns, and any other function panics if it can reach
+/// its end without returning a value.
+pub struct FunctionBuilder {
+    ctx: Context,
+    func: Function,
+    current: Option<Block>,
+    previous: Option<Block>,
+    loops: Vec<Loop>,
+    blocks: usize,
+}
+
+impl FunctionBuilder {
+    /// Starts building a function with no blocks yet, in a new block named
+    /// "entry".
+    pub fn new(func: Function) -> FunctionBuilder {
+        let ctx = func.to_object().get_context();
+        let entry = func.new_block("entry");
+        let builder = FunctionBuilder {
+            ctx,
+            func,
+            current: Some(entry),
+            previous: ctx.current_block(),
+            loops: Vec::new(),
+            blocks: 0,
+        };
+        ctx.set_current_block(Some(entry));
+        builder
+    }
+
+    pub fn function(&self) -> Function {
+        self.func
+    }
+
+    /// Gets the block that code is being added to. Panics after a return,
+    /// break or continue, since code added there could never run.
+    pub fn block(&self) -> Block {
+        match self.current() {
+            Some(block) => {
+                self.ctx.set_current_block(Some(block));
+                block
+            }
+            None => panic!(
+                "code after a return, break or continue in {:?} is unreachable",
+                self.func
+            ),
+        }
+    }
+
+    /// Returns true while code added to block() can be reached, i.e. until
+    /// a return, break or continue, or until block() is ended by hand.
+    pub fn is_reachable(&self) -> bool {
+        self.current().is_some()
+    }
+
+    /// Adds `if (cond) { then }`. The condition may be any scalar.
+    pub fn if_then<C, T>(&mut self, loc: Option<Location>, cond: C, then: T)
+    where
+        C: ToRValue,
+        T: FnOnce(&mut FunctionBuilder),
+    {
+        let then_block = self.new_block("then");
+        let after = self.new_block("after_if");
+        self.block()
+            .end_with_promoted_conditional(loc, cond, then_block, after);
+        self.switch_to(Some(then_block));
+        then(self);
+        self.jump_to_block(loc, after);
+        self.switch_to(Some(after));
+    }
+
+    /// Adds `if (cond) { then } else { otherwise }`. The condition may be
+    /// any scalar.
+    pub fn if_then_else<C, T, E>(&mut self, loc: Option<Location>, cond: C, then: T, otherwise: E)
+    where
+        C: ToRValue,
+        T: FnOnce(&mut FunctionBuilder),
+        E: FnOnce(&mut FunctionBuilder),
+    {
+        let then_block = self.new_block("then");
+        let else_block = self.new_block("else");
+        self.block()
+            .end_with_promoted_conditional(loc, cond, then_block, else_block);
+        let mut after = self.target("after_if");
+        self.switch_to(Some(then_block));
+        then(self);
+        self.jump_to(loc, &mut after);
+        self.switch_to(Some(else_block));
+        otherwise(self);
+        self.jump_to(loc, &mut after);
+        self.switch_to(after.block);
+    }
+
+    /// Adds `while (cond) { body }`. The condition is evaluated before each
+    /// iteration, so it is usually an expression over locals, such as
+    /// `i.to_rvalue().lt(n)`.
+    pub fn while_loop<C, B>(&mut self, loc: Option<Location>, cond: C, body: B)
+    where
+        C: ToRValue,
+        B: FnOnce(&mut FunctionBuilder),
+    {
+        let header = self.new_block("while");
+        let body_block = self.new_block("while_body");
+        let exit = self.new_block("after_while");
+        self.block().end_with_jump(loc, header);
+        header.end_with_promoted_conditional(loc, cond, body_block, exit);
+        self.run_loop(
+            loc,
+            body_block,
+            Target::created(header),
+            Target::created(exit),
+            body,
+        );
+    }
+
+    /// Adds `for (name = start; name < end; name++) { body }`, with a new
+    /// local of the type of `start`. The body is given the value of the
+    /// counter. `end` is converted to that type and evaluated before each
+    /// iteration; `continue_` goes on to the next value.
+    pub fn for_range<S, E, B>(
+        &mut self,
+        loc: Option<Location>,
+        name: &str,
+        start: S,
+        end: E,
+        body: B,
+    ) where
+        S: ToRValue,
+        E: ToRValue,
+        B: FnOnce(&mut FunctionBuilder, RValue),
+    {
+        let start = start.to_rvalue();
+        let ty = start.get_type();
+        let end = end.to_rvalue();
+        let end = if end.get_type() == ty {
+            end
+        } else {
+            self.ctx.new_cast(loc, end, ty)
+        };
+        let counter = self.func.new_local(loc, ty, name);
+        self.block().add_assignment(loc, counter, start);
+        let header = self.new_block("for");
+        let body_block = self.new_block("for_body");
+        let exit = self.new_block("after_for");
+        self.block().end_with_jump(loc, header);
+        let in_range = self
+            .ctx
+            .new_comparison(loc, ComparisonOp::LessThan, counter, end);
+        header.end_with_conditional(loc, in_range, body_block, exit);
+
+        let step = self.target("for_step");
+        self.loops.push(Loop {
+            continue_to: step,
+            break_to: Target::created(exit),
+        });
+        self.switch_to(Some(body_block));
+        body(self, counter.to_rvalue());
+        let mut frame = self.loops.pop().unwrap();
+        self.jump_to(loc, &mut frame.continue_to);
+        if let Some(step) = frame.continue_to.block {
+            let one = self.ctx.new_rvalue_one(ty);
+            let next = self
+                .ctx
+                .new_binary_op(loc, BinaryOp::Plus, ty, counter, one);
+            step.add_assignment(loc, counter, next);
+            step.end_with_jump(loc, header);
+        }
+        self.switch_to(Some(exit));
+    }
+
+    /// Adds `loop { body }`, which only ends through `break_` or a return.
+    pub fn loop_<B>(&mut self, loc: Option<Location>, body: B)
+    where
+        B: FnOnce(&mut FunctionBuilder),
+    {
+        let body_block = self.new_block("loop");
+        self.block().end_with_jump(loc, body_block);
+        let exit = self.target("after_loop");
+        self.run_loop(loc, body_block, Target::created(body_block), exit, body);
+    }
+
+    /// Jumps out of the innermost loop. Panics outside of a loop.
+    pub fn break_(&mut self, loc: Option<Location>) {
+        let mut frame = self.innermost_loop("break_");
+        self.jump_to(loc, &mut frame.break_to);
+        self.loops.push(frame);
+        self.switch_to(None);
+    }
+
+    /// Jumps to the next iteration of the innermost loop. Panics outside of
+    /// a loop.
+    pub fn continue_(&mut self, loc: Option<Location>) {
+        let mut frame = self.innermost_loop("continue_");
+        self.jump_to(loc, &mut frame.continue_to);
+        self.loops.push(frame);
+        self.switch_to(None);
+    }
+
+    /// Returns `value` from the function.
+    pub fn return_<T: ToRValue>(&mut self, loc: Option<Location>, value: T) {
+        self.block().end_with_return(loc, value);
+        self.switch_to(None);
+    }
+
+    /// Returns from a void function.
+    pub fn return_void(&mut self, loc: Option<Location>) {
+        self.block().end_with_void_return(loc);
+        self.switch_to(None);
+    }
+
+    /// Terminates the last block, see FunctionBuilder. Dropping the builder
+    /// does the same.
+    pub fn finish(self) {}
+
+    fn run_loop<B>(
+        &mut self,
+        loc: Option<Location>,
+        body_block: Block,
+        continue_to: Target,
+        break_to: Target,
+        body: B,
+    ) where
+        B: FnOnce(&mut FunctionBuilder),
+    {
+        self.loops.push(Loop {
+            continue_to,
+            break_to,
+        });
+        self.switch_to(Some(body_block));
+        body(self);
+        let mut frame = self.loops.pop().unwrap();
+        self.jump_to(loc, &mut frame.continue_to);
+        self.switch_to(frame.break_to.block);
+    }
+
+    fn innermost_loop(&mut self, what: &str) -> Loop {
+        match self.loops.pop() {
+            Some(frame) => frame,
+            None => panic!("{} outside of a loop in {:?}", what, self.func),
+        }
+    }
+
+    fn new_block(&mut self, name: &str) -> Block {
+        self.blocks += 1;
+        self.func.new_block(format!("{}_{}", name, self.blocks))
+    }
+
+    fn target(&mut self, name: &str) -> Target {
+        self.blocks += 1;
+        Target {
+            name: format!("{}_{}", name, self.blocks),
+            block: None,
+        }
+    }
+
+    /// The block code is being added to, unless it has been ended.
+    fn current(&self) -> Option<Block> {
+        self.current.filter(|block| !block.is_terminated())
+    }
+
+    /// Ends the current block with a jump to the target, if it is reachable.
+    fn jump_to(&mut self, loc: Option<Location>, target: &mut Target) {
+        if let Some(current) = self.current() {
+            let block = match target.block {
+                Some(block) => block,
+                None => self.func.new_block(&target.name),
+            };
+            target.block = Some(block);
+            current.end_with_jump(loc, block);
+        }
+    }
+
+    fn jump_to_block(&mut self, loc: Option<Location>, block: Block) {
+        if let Some(current) = self.current() {
+            current.end_with_jump(loc, block);
+        }
+    }
+
+    fn switch_to(&mut self, block: Option<Block>) {
+        self.current = block;
+        self.ctx.set_current_block(block);
+    }
+}
+
+impl Target {
+    fn created(block: Block) -> Target {
+        Target {
+            name: String::new(),
+            block: Some(block),
+        }
+    }
+}
+
+impl Drop for FunctionBuilder {
+    fn drop(&mut self) {
+        let current = match self.current() {
+            Some(current) => current,
+            None => {
+                self.ctx.set_current_block(self.previous);
+                return;
+            }
+        };
+        let void = self.ctx.new_type::<()>();
+        match self.func.get_return_type() {
+            ty if ty == void => current.end_with_void_return(None),
+            _ if std::thread::panicking() => {}
+            _ => panic!(
+                "{:?} can reach its end without returning a value",
+                self.func
+            ),
+        }
+        self.ctx.set_current_block(self.previous);
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ctx::{self, GlobalKind};
+use crate::field::{self, Field};
+use crate::function::{self, Function, FunctionType};
+use crate::lvalue::{self, LValue};
+use crate::object::ToObject;
+use crate::parameter::{self, Parameter};
+use crate::structs::Struct;
+use crate::ty::{self as types, Type};
+
+/// CDecl is implemented by objects that can be written out as a C
+/// declaration, e.g. for error messages or for a header that goes with
+/// a library built by compile_to_file.
+pub trait CDecl {
+    fn c_decl(&self) -> String;
+}
+
+/// A type renders as an abstract declarator, such as `int (*)(char *)`.
+impl CDecl for Type {
+    fn c_decl(&self) -> String {
+        declare(*self, "")
+    }
+}
+
+/// A struct renders as its definition, or as a forward declaration if its
+/// fields were never set.
+impl CDecl for Struct {
+    fn c_decl(&self) -> String {
+        let ty = self.as_type();
+        match members(ty) {
+            Some(fields) => {
+                let mut out = format!("{} {{\n", debug(ty));
+                for f in fields {
+                    out.push_str("    ");
+                    out.push_str(&declare_field(f));
+                    out.push_str(";\n");
+                }
+                out.push_str("};");
+                out
+            }
+            None => format!("{};", debug(ty)),
+        }
+    }
+}
+
+/// A function renders as its prototype, with `static` for internal
+/// functions and `extern` for imported ones.
+impl CDecl for Function {
+    fn c_decl(&self) -> String {
+        let ptr = unsafe { function::get_ptr(self) } as usize;
+        let sig = with_decls(*self, |decls| decls.functions.get(&ptr).cloned());
+        let sig = match sig {
+            Some(sig) => sig,
+            None => return format!("{:?};", self),
+        };
+        let prefix = match sig.kind {
+            FunctionType::Exported => "",
+            FunctionType::Internal => "static ",
+            FunctionType::Extern => "extern ",
+            FunctionType::AlwaysInline => "static inline ",
+        };
+        let params: Vec<_> = sig.params.iter().map(|&p| declare_param(p)).collect();
+        let declarator = format!(
+            "{}({})",
+            debug(*self),
+            param_list(params, sig.is_variadic)
+        );
+        format!("{}{};", prefix, declare(sig.return_type, &declarator))
+    }
+}
+
+/// A global renders as a variable declaration, with `static` for internal
+/// globals and `extern` for imported ones.
+impl CDecl for LValue {
+    fn c_decl(&self) -> String {
+        let ptr = unsafe { lvalue::get_ptr(self) } as usize;
+        let global = with_decls(*self, |decls| decls.globals.get(&ptr).cloned());
+        match global {
+            Some((ty, kind)) => {
+                let prefix = match kind {
+                    GlobalKind::Exported => "",
+                    GlobalKind::Internal => "static ",
+                    GlobalKind::External => "extern ",
+                };
+                format!("{}{};", prefix, declare(ty, &debug(*self)))
+            }
+            None => format!("{:?};", self),
+        }
+    }
+}
+
+/// Renders a declaration of `name` with the given type, such as
+/// `int (*name[4])(int)`. An empty name gives an abstract declarator.
+pub fn declare(ty: Type, name: &str) -> String {
+    render(ty, name.to_string())
+}
+
+/// How a type was built, for the types whose gccjit debug string loses
+/// information: array bounds, and where qualifiers and `*` go.
+#[derive(Clone)]
+pub(crate) enum Shape {
+    Pointer(Type),
+    Const(Type),
+    Volatile(Type),
+    Array(Type, i32),
+    FunctionPtr {
+        return_type: Type,
+        params: Vec<Type>,
+        is_variadic: bool,
+    },
+}
+
+#[derive(Clone)]
+struct Signature {
+    kind: FunctionType,
+    return_type: Type,
+    params: Vec<Parameter>,
+    is_variadic: bool,
+}
+
+struct FieldDecl {
+    name: String,
+    ty: Type,
+    width: Option<u32>,
+}
+
+#[derive(Default)]
+struct Decls {
+    shapes: HashMap<usize, Shape>,
+    fields: HashMap<usize, FieldDecl>,
+    members: HashMap<usize, Vec<Field>>,
+    unions: HashSet<usize>,
+    params: HashMap<usize, Type>,
+    functions: HashMap<usize, Signature>,
+    globals: HashMap<usize, (Type, GlobalKind)>,
+}
+
+/// Runs `f` on what was recorded about the context `obj` belongs to. The
+/// records are dropped with the context, so the addresses they are keyed
+/// by always belong to live objects.
+fn with_decls<R>(obj: impl ToObject, f: impl FnOnce(&mut Decls) -> R) -> R {
+    let ctx = obj.to_object().get_context();
+    let decls = ctx::context_state::<Decls>(&ctx);
+    let mut decls = decls.borrow_mut();
+    f(&mut decls)
+}
+
+fn key(ty: Type) -> usize {
+    unsafe { types::get_ptr(&ty) as usize }
+}
+
+pub(crate) fn record_shape(ty: Type, shape: Shape) {
+    with_decls(ty, |decls| decls.shapes.insert(key(ty), shape));
+}
+
+pub(crate) fn record_field(field: Field, name: &str, ty: Type, width: Option<u32>) {
+    let ptr = unsafe { field::get_ptr(&field) } as usize;
+    let decl = FieldDecl {
+        name: name.to_owned(),
+        ty,
+        width,
+    };
+    with_decls(field, |decls| decls.fields.insert(ptr, decl));
+}
+
+pub(crate) fn record_members(ty: Type, fields: &[Field]) {
+    with_decls(ty, |decls| decls.members.insert(key(ty), fields.to_vec()));
+}
+
+/// Gets the fields a struct or union was created with, in order.
+pub(crate) fn members(ty: Type) -> Option<Vec<Field>> {
+    with_decls(ty, |decls| decls.members.get(&key(ty)).cloned())
+}
+
+/// Finds the field of a struct or union type by name, looking through
+/// qualifiers. Panics with the names of the fields there are if there is
+/// no such field, or if the type has no fields this crate knows of.
+pub(crate) fn field_named(ty: Type, name: &str) -> Field {
+    let mut record = ty;
+    while let Some(Shape::Const(inner)) | Some(Shape::Volatile(inner)) = shape(record) {
+        record = inner;
+    }
+    let fields = match members(record) {
+        Some(fields) => fields,
+        None => panic!(
+            "cannot look up field `{}`: {:?} is not a struct or union with known fields",
+            name, ty
+        ),
+    };
+    let names: Vec<_> = fields
+        .iter()
+        .map(|&field| field_name(field).unwrap_or_default())
+        .collect();
+    match names.iter().position(|field| field == name) {
+        Some(index) => fields[index],
+        None => {
+            panic!(
+                "{:?} has no field `{}`; its fields are: {}",
+                ty,
+                name,
+                names.join(", ")
+            )
+        }
+    }
+}
+
+pub(crate) fn record_union(ty: Type) {
+    with_decls(ty, |decls| decls.unions.insert(key(ty)));
+}
+
+pub(crate) fn is_union(ty: Type) -> bool {
+    with_decls(ty, |decls| decls.unions.contains(&key(ty)))
+}
+
+/// Gets the type and bit-field width a field was created with.
+pub(crate) fn field_type(field: Field) -> Option<(Type, Option<u32>)> {
+    let ptr = unsafe { field::get_ptr(&field) } as usize;
+    with_decls(field, |decls| {
+        decls
+            .fields
+            .get(&ptr)
+            .map(|decl| (decl.ty, decl.width))
+    })
+}
+
+/// Gets the name a field was created with.
+pub(crate) fn field_name(field: Field) -> Option<String> {
+    let ptr = unsafe { field::get_ptr(&field) } as usize;
+    with_decls(field, |decls| decls.fields.get(&ptr).map(|decl| decl.name.clone()))
+}
+
+pub(crate) fn record_param(param: Parameter, ty: Type) {
+    let ptr = unsafe { parameter::get_ptr(&param) } as usize;
+    with_decls(param, |decls| decls.params.insert(ptr, ty));
+}
+
+pub(crate) fn record_function(
+    func: Function,
+    kind: FunctionType,
+    return_type: Type,
+    params: &[Parameter],
+    is_variadic: bool,
+) {
+    let ptr = unsafe { function::get_ptr(&func) } as usize;
+    let sig = Signature {
+        kind,
+        return_type,
+        params: params.to_vec(),
+        is_variadic,
+    };
+    with_decls(func, |decls| decls.functions.insert(ptr, sig));
+}
+
+pub(crate) fn record_global(global: LValue, ty: Type, kind: GlobalKind) {
+    let ptr = unsafe { lvalue::get_ptr(&global) } as usize;
+    with_decls(global, |decls| decls.globals.insert(ptr, (ty, kind)));
+}
+
+pub(crate) fn shape(ty: Type) -> Option<Shape> {
+    with_decls(ty, |decls| decls.shapes.get(&key(ty)).cloned())
+}
+
+/// Returns true if the two types are the same C type. gccjit makes a new
+/// object every time a const, volatile, array or function pointer type is
+/// asked for, so those are compared by how they were built.
+pub(crate) fn same_type(a: Type, b: Type) -> bool {
+    if a == b {
+        return true;
+    }
+    match (shape(a), shape(b)) {
+        (Some(Shape::Pointer(a)), Some(Shape::Pointer(b)))
+        | (Some(Shape::Const(a)), Some(Shape::Const(b)))
+        | (Some(Shape::Volatile(a)), Some(Shape::Volatile(b))) => same_type(a, b),
+        (Some(Shape::Array(a, a_len)), Some(Shape::Array(b, b_len))) => {
+            a_len == b_len && same_type(a, b)
+        }
+        (
+            Some(Shape::FunctionPtr {
+                return_type: a_return,
+                params: a_params,
+                is_variadic: a_variadic,
+            }),
+            Some(Shape::FunctionPtr {
+                return_type: b_return,
+                params: b_params,
+                is_variadic: b_variadic,
+            }),
+        ) => {
+            a_variadic == b_variadic
+                && a_params.len() == b_params.len()
+                && same_type(a_return, b_return)
+                && a_params.iter().zip(&b_params).all(|(&a, &b)| same_type(a, b))
+        }
+        _ => false,
+    }
+}
+
+fn debug(obj: impl ToObject) -> String {
+    format!("{:?}", obj.to_object())
+}
+
+/// Wraps `declarator` in the type, inside out: arrays and function
+/// parameter lists bind tighter than `*`, so a pointer to either needs
+/// parentheses.
+fn render(ty: Type, declarator: String) -> String {
+    match shape(ty) {
+        Some(Shape::Pointer(pointee)) => render_pointer(pointee, "", declarator),
+        Some(Shape::Const(inner)) => render_qualified(inner, "const", declarator),
+        Some(Shape::Volatile(inner)) => render_qualified(inner, "volatile", declarator),
+        Some(Shape::Array(element, len)) => render(element, format!("{}[{}]", declarator, len)),
+        Some(Shape::FunctionPtr {
+            return_type,
+            params,
+            is_variadic,
+        }) => render_function_ptr(return_type, &params, is_variadic, "", declarator),
+        None => {
+            let base = debug(ty);
+            if declarator.is_empty() {
+                base
+            } else {
+                format!("{} {}", base, declarator)
+            }
+        }
+    }
+}
+
+/// Qualifiers on a pointer go after the `*`, qualifiers on anything else
+/// go in front of it.
+fn render_qualified(inner: Type, qualifier: &str, declarator: String) -> String {
+    match shape(inner) {
+        Some(Shape::Pointer(pointee)) => render_pointer(pointee, qualifier, declarator),
+        Some(Shape::FunctionPtr {
+            return_type,
+            params,
+            is_variadic,
+        }) => render_function_ptr(return_type, &params, is_variadic, qualifier, declarator),
+        _ => format!("{} {}", qualifier, render(inner, declarator)),
+    }
+}
+
+fn pointer_declarator(qualifier: &str, declarator: String) -> String {
+    match (qualifier.is_empty(), declarator.is_empty()) {
+        (true, _) => format!("*{}", declarator),
+        (false, true) => format!("* {}", qualifier),
+        (false, false) => format!("* {} {}", qualifier, declarator),
+    }
+}
+
+fn render_pointer(pointee: Type, qualifier: &str, declarator: String) -> String {
+    let declarator = pointer_declarator(qualifier, declarator);
+    match shape(pointee) {
+        Some(Shape::Array(..)) | Some(Shape::FunctionPtr { .. }) => {
+            render(pointee, format!("({})", declarator))
+        }
+        _ => render(pointee, declarator),
+    }
+}
+
+fn render_function_ptr(
+    return_type: Type,
+    params: &[Type],
+    is_variadic: bool,
+    qualifier: &str,
+    declarator: String,
+) -> String {
+    let params: Vec<_> = params.iter().map(|&p| render(p, String::new())).collect();
+    let declarator = format!(
+        "({})({})",
+        pointer_declarator(qualifier, declarator),
+        param_list(params, is_variadic)
+    );
+    render(return_type, declarator)
+}
+
+fn param_list(mut params: Vec<String>, is_variadic: bool) -> String {
+    if is_variadic {
+        params.push("...".to_string());
+    }
+    if params.is_empty() {
+        "void".to_string()
+    } else {
+        params.join(", ")
+    }
+}
+
+fn declare_param(param: Parameter) -> String {
+    let ptr = unsafe { parameter::get_ptr(&param) } as usize;
+    match with_decls(param, |decls| decls.params.get(&ptr).copied()) {
+        Some(ty) => declare(ty, &debug(param)),
+        None => debug(param),
+    }
+}
+
+fn declare_field(field: Field) -> String {
+    let name = field_name(field).unwrap_or_else(|| debug(field));
+    match field_type(field) {
+        Some((ty, None)) => declare(ty, &name),
+        Some((ty, Some(width))) => format!("{} : {}", declare(ty, &name), width),
+        None => name,
+    }
+}
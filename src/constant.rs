@@ -0,0 +1,200 @@
+use std::fmt;
+use std::mem;
+use std::ptr;
+
+use crate::cdecl::{self, Shape};
+use crate::ctx::Context;
+use crate::field::Field;
+use crate::layout;
+use crate::rvalue::RValue;
+use crate::ty::{Complex, Type};
+
+/// IntoConst is implemented by Rust values that can become gccjit constants.
+/// The value is converted to `ty` the way a C cast would; integers keep
+/// their full width and floats their exact bits. Floats panic for integer
+/// types instead of truncating, like float literal operands do.
+pub trait IntoConst {
+    fn into_const(self, ctx: &Context, ty: Type) -> RValue;
+}
+
+macro_rules! signed_into_const {
+    ($($ty:ty),*) => {
+        $(impl IntoConst for $ty {
+            fn into_const(self, ctx: &Context, ty: Type) -> RValue {
+                ctx.new_rvalue_from_i128(ty, self as i128)
+            }
+        })*
+    };
+}
+
+macro_rules! unsigned_into_const {
+    ($($ty:ty),*) => {
+        $(impl IntoConst for $ty {
+            fn into_const(self, ctx: &Context, ty: Type) -> RValue {
+                ctx.new_rvalue_from_u128(ty, self as u128)
+            }
+        })*
+    };
+}
+
+signed_into_const!(i8, i16, i32, i64, isize, i128);
+unsigned_into_const!(u8, u16, u32, u64, usize, u128);
+
+/// Panics if a float is made a constant of an integer type, which would
+/// silently drop its fraction.
+fn check_float_type<F: fmt::Debug>(value: F, ty: Type) {
+    if ty.unqualified().is_integral() {
+        panic!(
+            "the float {:?} can't be a constant of integer type {:?}, round it first",
+            value, ty
+        );
+    }
+}
+
+impl IntoConst for f32 {
+    fn into_const(self, ctx: &Context, ty: Type) -> RValue {
+        check_float_type(self, ty);
+        ctx.new_rvalue_from_f32(ty, self)
+    }
+}
+
+impl IntoConst for f64 {
+    fn into_const(self, ctx: &Context, ty: Type) -> RValue {
+        check_float_type(self, ty);
+        ctx.new_rvalue_from_double(ty, self)
+    }
+}
+
+impl IntoConst for bool {
+    fn into_const(self, ctx: &Context, ty: Type) -> RValue {
+        let value = ctx.new_rvalue_from_int(ctx.new_type::<i32>(), self as i32);
+        ctx.new_cast(None, value, ty)
+    }
+}
+
+impl IntoConst for Complex<f32> {
+    fn into_const(self, ctx: &Context, ty: Type) -> RValue {
+        ctx.new_rvalue_from_complex(ty, Complex::new(self.re as f64, self.im as f64))
+    }
+}
+
+impl IntoConst for Complex<f64> {
+    fn into_const(self, ctx: &Context, ty: Type) -> RValue {
+        ctx.new_rvalue_from_complex(ty, self)
+    }
+}
+
+/// Builds a constant of type `ty` out of the bytes of a value of that type,
+/// which must be laid out the way gccjit lays out `ty`. Structs and arrays
+/// become constructors of their parts. Unions take the value of their
+/// largest field, the first one if several are equally large. Padding is
+/// never read, including padding inside that field.
+///
+/// Only types this crate has seen being built can be walked, so a type
+/// from e.g. `gcc_jit_type_get_aligned` panics, as do bit-fields.
+pub(crate) unsafe fn from_raw(ctx: &Context, ty: Type, value: *const u8) -> RValue {
+    match cdecl::shape(ty) {
+        Some(Shape::Const(inner)) | Some(Shape::Volatile(inner)) => {
+            return from_raw(ctx, inner, value)
+        }
+        Some(Shape::Array(element, len)) => {
+            let stride = layout::size_of(element);
+            let elements: Vec<_> = (0..len as usize)
+                .map(|i| from_raw(ctx, element, value.add(i * stride)))
+                .collect();
+            return ctx.new_array_constructor(None, ty, &elements);
+        }
+        _ => {}
+    }
+    if ty.is_pointer() {
+        let address = read_uint(value, mem::size_of::<usize>());
+        return ctx.new_rvalue_from_ptr(ty, address as usize as *mut ());
+    }
+
+    if ty == ctx.new_type::<f32>() {
+        let bits = read_uint(value, 4) as u32;
+        return ctx.new_rvalue_from_f32(ty, f32::from_bits(bits));
+    }
+    if ty == ctx.new_type::<f64>() {
+        let bits = read_uint(value, 8);
+        return ctx.new_rvalue_from_double(ty, f64::from_bits(bits));
+    }
+    if ty == ctx.new_type::<bool>() {
+        let int = ctx.new_type::<i32>();
+        let value = ctx.new_rvalue_from_int(int, (*value != 0) as i32);
+        return ctx.new_cast(None, value, ty);
+    }
+    if ty.is_integral() && layout::size_of(ty) == 16 {
+        let (low, high) = (read_uint(value, 8), read_uint(value.add(8), 8));
+        let (low, high) = if cfg!(target_endian = "little") {
+            (low, high)
+        } else {
+            (high, low)
+        };
+        let bits = (high as u128) << 64 | low as u128;
+        return ctx.new_rvalue_from_u128(ty, bits);
+    }
+    if ty.is_integral() {
+        let size = layout::size_of(ty);
+        let bits = read_uint(value, size);
+        let bits = if ty.is_signed() && size < 8 {
+            let shift = 64 - 8 * size as u32;
+            ((bits << shift) as i64 >> shift) as u64
+        } else {
+            bits
+        };
+        return ctx.new_rvalue_from_long(ty, bits as i64);
+    }
+
+    let fields = match cdecl::members(ty) {
+        Some(fields) => fields,
+        None => panic!("cannot build a constant of type {:?}", ty),
+    };
+    for &field in &fields {
+        if let Some((_, Some(_))) = cdecl::field_type(field) {
+            panic!(
+                "cannot build a constant of type {:?}: bit-field {:?} has no address",
+                ty, field
+            );
+        }
+    }
+    let field_ty = |field| match cdecl::field_type(field) {
+        Some((ty, _)) => ty,
+        None => panic!("cannot build a constant for field {:?}", field),
+    };
+    if cdecl::is_union(ty) {
+        // The first of the largest members, so that every byte of the value
+        // is carried over except for padding within that member.
+        let mut largest: Option<(Field, usize)> = None;
+        for &field in &fields {
+            let size = layout::size_of(field_ty(field));
+            if largest.is_none_or(|(_, largest_size)| size > largest_size) {
+                largest = Some((field, size));
+            }
+        }
+        let field = match largest {
+            Some((field, _)) => field,
+            None => return ctx.new_union_constructor(None, ty, None),
+        };
+        let part = from_raw(ctx, field_ty(field), value);
+        return ctx.new_union_constructor(None, ty, Some((field, part)));
+    }
+    let offsets = layout::field_offsets(ty, &fields);
+    let values: Vec<_> = fields
+        .iter()
+        .zip(offsets)
+        .map(|(&field, offset)| (field, from_raw(ctx, field_ty(field), value.add(offset))))
+        .collect();
+    ctx.new_struct_constructor(None, ty, &values)
+}
+
+/// Reads a native-endian unsigned integer of `size` bytes.
+unsafe fn read_uint(value: *const u8, size: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    if cfg!(target_endian = "little") {
+        ptr::copy_nonoverlapping(value, bytes.as_mut_ptr(), size);
+    } else {
+        ptr::copy_nonoverlapping(value, bytes.as_mut_ptr().add(8 - size), size);
+    }
+    u64::from_ne_bytes(bytes)
+}
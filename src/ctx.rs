@@ -1,23 +1,34 @@
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::default::Default;
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::marker::PhantomData;
 use std::mem;
 use std::ops::Drop;
 use std::ptr;
+use std::rc::Rc;
 
 use crate::block::{BinaryOp, Block, Case, ComparisonOp, UnaryOp};
+use crate::cdecl::{self, Shape};
+use crate::constant::{self, IntoConst};
+use crate::enums::{self, EnumType};
 use crate::field::{self, Field};
 use crate::function::{self, Function, FunctionType};
+use crate::layout;
 use crate::location::{self, Location};
 use crate::lvalue::{self, LValue};
 use crate::parameter::{self, Parameter};
+use crate::promote;
 use crate::rvalue::{self, RValue, ToRValue};
-use crate::structs::{self, Struct};
+use crate::structs::{self, OpaqueStruct, Struct, Union};
 use crate::ty as types;
 use gccjit_sys::*;
 
 use crate::sys::*;
 
+#[derive(Copy, Clone, Debug)]
 #[repr(C)]
 pub enum GlobalKind {
     Exported = 0,
@@ -25,6 +36,29 @@ pub enum GlobalKind {
     Internal = 1,
 }
 
+/// The ELF visibility of a symbol, as in GCC's `visibility` attribute.
+/// Hidden symbols can be used from anywhere in the object they are defined
+/// in, but are not exported from it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Visibility {
+    Default,
+    Hidden,
+    Protected,
+    Internal,
+}
+
+impl Visibility {
+    pub(crate) fn as_cstr(self) -> &'static CStr {
+        let name: &'static [u8] = match self {
+            Visibility::Default => b"default\0",
+            Visibility::Hidden => b"hidden\0",
+            Visibility::Protected => b"protected\0",
+            Visibility::Internal => b"internal\0",
+        };
+        CStr::from_bytes_with_nul(name).unwrap()
+    }
+}
+
 /// Represents an optimization level that the JIT compiler
 /// will use when compiling your code.
 #[repr(C)]
@@ -100,7 +134,10 @@ impl Context {
     /// Compiles the context and returns a CompileResult that contains
     /// the means to access functions and globals that have currently
     /// been JIT compiled.
+    ///
+    /// Panics if a struct made by new_opaque_struct_type was never completed.
     pub fn compile(&self) -> CompileResult {
+        structs::check_complete(self);
         unsafe {
             CompileResult {
                 ptr: gccjit_sys::gcc_jit_context_compile(self.ptr),
@@ -110,7 +147,10 @@ impl Context {
 
     /// Compiles the context and saves the result to a file. The
     /// type of the file is controlled by the OutputKind parameter.
+    ///
+    /// Panics if a struct made by new_opaque_struct_type was never completed.
     pub fn compile_to_file<S: AsRef<str>>(&self, kind: OutputKind, file: S) {
+        structs::check_complete(self);
         unsafe {
             let file_ref = file.as_ref();
             let cstr = CString::new(file_ref).unwrap();
@@ -152,6 +192,34 @@ impl Context {
         }
     }
 
+    /// Releases the context, every object made in it, and everything this
+    /// crate keeps about it. Results compiled from it stay valid, and are
+    /// released when they are dropped.
+    ///
+    /// # Safety
+    ///
+    /// Contexts and the objects made in them are Copy handles, so nothing
+    /// stops a copy from being used afterwards, which is a use after free.
+    /// Child contexts must be released before their parent.
+    pub unsafe fn release(self) {
+        let state = STATES.with(|states| states.borrow_mut().remove(&(self.ptr as usize)));
+        drop(state);
+        gcc_jit_context_release(self.ptr);
+    }
+
+    /// Returns the first error message reported on this context, if any.
+    /// After an error, compile() produces a CompileResult with no code.
+    pub fn get_first_error(&self) -> Option<String> {
+        unsafe {
+            let ptr = gcc_jit_context_get_first_error(self.ptr);
+            if ptr.is_null() {
+                None
+            } else {
+                Some(std::ffi::CStr::from_ptr(ptr).to_string_lossy().into_owned())
+            }
+        }
+    }
+
     /// Creates a new child context from this context. The child context
     /// is a fully-featured context, but it has a lifetime that is strictly
     /// less than the lifetime that spawned it.
@@ -170,7 +238,7 @@ impl Context {
         ty: types::Type,
         name: &str,
     ) -> LValue {
-        unsafe {
+        let global = unsafe {
             let cstr = std::ffi::CString::new(name).unwrap();
             lvalue::from_ptr(gcc_jit_context_new_global(
                 self.ptr,
@@ -179,7 +247,37 @@ impl Context {
                 types::get_ptr(&ty),
                 cstr.as_ptr(),
             ))
+        };
+        cdecl::record_global(global, ty, kind);
+        global
+    }
+
+    /// Creates a global of type T whose initial value is `value`, baked into
+    /// the compiled code. T's gccjit type must have been built by this crate,
+    /// e.g. through Typeable, so that its parts can be turned into constants.
+    /// Pointers keep their address, which is only meaningful in this process.
+    pub fn new_global_with_value<T: types::Typeable + Copy>(
+        &self,
+        loc: Option<Location>,
+        kind: GlobalKind,
+        name: &str,
+        value: T,
+    ) -> LValue {
+        let ty = T::get_type(self);
+        let size = layout::size_of(ty);
+        if size != mem::size_of::<T>() {
+            panic!(
+                "{} is {} bytes but {:?} is {}",
+                std::any::type_name::<T>(),
+                mem::size_of::<T>(),
+                ty,
+                size
+            );
         }
+        let global = self.new_global(loc, kind, ty, name);
+        let init = unsafe { constant::from_raw(self, ty, &value as *const T as *const u8) };
+        global.set_initializer_rvalue(init);
+        global
     }
 
     /// Creates a new location for use by gdb when debugging a JIT compiled
@@ -225,7 +323,7 @@ impl Context {
             Some(loc) => unsafe { location::get_ptr(&loc) },
             None => ptr::null_mut(),
         };
-        unsafe {
+        let array = unsafe {
             let ptr = gccjit_sys::gcc_jit_context_new_array_type(
                 self.ptr,
                 loc_ptr,
@@ -233,7 +331,9 @@ impl Context {
                 num_elements,
             );
             types::from_ptr(ptr)
-        }
+        };
+        cdecl::record_shape(array, Shape::Array(ty, num_elements));
+        array
     }
 
     /// Constructs a new struct type with the given name, optional source location,
@@ -264,13 +364,20 @@ impl Context {
                 num_fields,
                 fields_ptrs.as_mut_ptr(),
             );
-            structs::from_ptr(ptr)
+            let s = structs::from_ptr(ptr);
+            cdecl::record_members(s.as_type(), fields);
+            s
         }
     }
 
     /// Constructs a new struct type whose fields are not known. Fields can
-    /// be added to this struct later, but only once.
-    pub fn new_opaque_struct_type<S: AsRef<str>>(&self, loc: Option<Location>, name: S) -> Struct {
+    /// be added to this struct later, but only once, which turns it into a
+    /// Struct.
+    pub fn new_opaque_struct_type<S: AsRef<str>>(
+        &self,
+        loc: Option<Location>,
+        name: S,
+    ) -> OpaqueStruct {
         let name_ref = name.as_ref();
         let loc_ptr = match loc {
             Some(loc) => unsafe { location::get_ptr(&loc) },
@@ -280,8 +387,48 @@ impl Context {
             let cstr = CString::new(name_ref).unwrap();
             let ptr =
                 gccjit_sys::gcc_jit_context_new_opaque_struct(self.ptr, loc_ptr, cstr.as_ptr());
-            structs::from_ptr(ptr)
+            structs::new_opaque(self, ptr, name_ref)
+        }
+    }
+
+    /// Constructs a group of structs that may refer to each other, such as
+    /// the nodes of a tree. All of the structs are declared first, and
+    /// `fields` is given their types to build the fields of each struct
+    /// from, in the same order as `names`.
+    ///
+    /// ```ignore
+    /// let list = ctx.new_recursive_struct_types(None, &["node"], |types| {
+    ///     let next = ctx.new_field(None, types[0].make_pointer(), "next");
+    ///     vec![vec![ctx.new_field(None, int, "value"), next]]
+    /// });
+    /// ```
+    pub fn new_recursive_struct_types<F>(
+        &self,
+        loc: Option<Location>,
+        names: &[&str],
+        fields: F,
+    ) -> Vec<Struct>
+    where
+        F: FnOnce(&[types::Type]) -> Vec<Vec<Field>>,
+    {
+        let opaque: Vec<_> = names
+            .iter()
+            .map(|name| self.new_opaque_struct_type(loc, name))
+            .collect();
+        let types: Vec<_> = opaque.iter().map(|s| s.as_type()).collect();
+        let fields = fields(&types);
+        if fields.len() != opaque.len() {
+            panic!(
+                "got fields for {} structs, but {} were declared",
+                fields.len(),
+                opaque.len()
+            );
         }
+        opaque
+            .into_iter()
+            .zip(fields.iter())
+            .map(|(s, fields)| s.set_fields(loc, fields))
+            .collect()
     }
 
     /// Creates a new union type from a set of fields.
@@ -290,7 +437,7 @@ impl Context {
         loc: Option<Location>,
         name: S,
         fields: &[Field],
-    ) -> types::Type {
+    ) -> Union {
         let name_ref = name.as_ref();
         let loc_ptr = match loc {
             Some(loc) => unsafe { location::get_ptr(&loc) },
@@ -310,10 +457,25 @@ impl Context {
                 num_fields,
                 fields_ptrs.as_mut_ptr(),
             );
-            types::from_ptr(ptr)
+            let union = types::from_ptr(ptr);
+            cdecl::record_members(union, fields);
+            cdecl::record_union(union);
+            structs::new_union(union)
         }
     }
 
+    /// Creates a C-style enum with the given underlying integer type and
+    /// variants. Panics if a variant's value does not fit in the underlying
+    /// type or if two variants have the same name.
+    pub fn new_enum_type<S: AsRef<str>>(
+        &self,
+        name: S,
+        underlying: types::Type,
+        variants: &[(&str, i128)],
+    ) -> EnumType {
+        enums::new(*self, name.as_ref(), underlying, variants)
+    }
+
     pub fn new_case(
         &self,
         min_value: impl ToRValue,
@@ -336,7 +498,7 @@ impl Context {
         ty: types::Type,
         name: impl AsRef<str>,
     ) -> Field {
-        unsafe {
+        let field = unsafe {
             let cstr = CString::new(name.as_ref()).unwrap();
             field::from_ptr(gcc_jit_context_new_field(
                 self.ptr,
@@ -344,7 +506,46 @@ impl Context {
                 types::get_ptr(&ty),
                 cstr.as_ptr(),
             ))
+        };
+        cdecl::record_field(field, name.as_ref(), ty, None);
+        field
+    }
+
+    /// Creates a new bit-field of the given integer type that is `width`
+    /// bits wide, like C's `ty name : width`. Bit-fields are read and written
+    /// with access_field like any other field, but their address can't be
+    /// taken. The type may be const or volatile qualified. Panics if the
+    /// type is not an integer type or if the width does not fit in it.
+    pub fn new_bitfield(
+        &self,
+        loc: Option<Location>,
+        ty: types::Type,
+        width: u32,
+        name: impl AsRef<str>,
+    ) -> Field {
+        let name_ref = name.as_ref();
+        if !ty.unqualified().is_integral() {
+            panic!("bit-field {} has non-integer type {:?}", name_ref, ty);
+        }
+        let bits = layout::size_of(ty) * 8;
+        if width == 0 || width as usize > bits {
+            panic!(
+                "bit-field {} is {} bits wide, but {:?} only has {} bits",
+                name_ref, width, ty, bits
+            );
         }
+        let field = unsafe {
+            let cstr = CString::new(name_ref).unwrap();
+            field::from_ptr(gcc_jit_context_new_bitfield(
+                self.ptr,
+                location::get_ptr(&loc.unwrap_or(location::from_ptr(ptr::null_mut()))),
+                types::get_ptr(&ty),
+                width as i32,
+                cstr.as_ptr(),
+            ))
+        };
+        cdecl::record_field(field, name_ref, ty, Some(width));
+        field
     }
 
     /// Creates a new function pointer type with the given return type
@@ -376,7 +577,14 @@ impl Context {
                 types_ptrs.as_mut_ptr(),
                 is_variadic as i32,
             );
-            types::from_ptr(ptr)
+            let ty = types::from_ptr(ptr);
+            let shape = Shape::FunctionPtr {
+                return_type,
+                params: param_types.to_vec(),
+                is_variadic,
+            };
+            cdecl::record_shape(ty, shape);
+            ty
         }
     }
 
@@ -413,7 +621,9 @@ impl Context {
                 params_ptrs.as_mut_ptr(),
                 is_variadic as i32,
             );
-            function::from_ptr(ptr)
+            let func = function::from_ptr(ptr);
+            cdecl::record_function(func, kind, return_ty, params, is_variadic);
+            func
         }
     }
 
@@ -495,6 +705,120 @@ impl Context {
         }
     }
 
+    /// Returns the type that C's usual arithmetic conversions give for a
+    /// binary operation on operands of the two types, or None if either is
+    /// not an arithmetic type.
+    pub fn common_arithmetic_type(
+        &self,
+        left: types::Type,
+        right: types::Type,
+    ) -> Option<types::Type> {
+        promote::common_type(self, left, right)
+    }
+
+    /// Casts both operands to their common type under C's usual arithmetic
+    /// conversions, and returns them with that type. Panics if they have
+    /// different types and aren't both arithmetic.
+    pub fn usual_arithmetic_conversions<L: ToRValue, R: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        left: L,
+        right: R,
+    ) -> (RValue, RValue, types::Type) {
+        promote::convert_operands(self, loc, left.to_rvalue(), right.to_rvalue())
+    }
+
+    /// Like new_binary_op, but the operands may have any arithmetic types:
+    /// they are converted as C would and the result type is picked for you.
+    /// Shifts take the promoted type of the left operand, which the right
+    /// operand is converted to as well, and `&&` and `||` give a bool.
+    pub fn new_promoted_binary_op<L: ToRValue, R: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        op: BinaryOp,
+        left: L,
+        right: R,
+    ) -> RValue {
+        promote::binary_op(self, loc, op, left.to_rvalue(), right.to_rvalue())
+    }
+
+    /// Like new_comparison, but the operands are first converted to their
+    /// common type as C would.
+    pub fn new_promoted_comparison<L: ToRValue, R: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        op: ComparisonOp,
+        left: L,
+        right: R,
+    ) -> RValue {
+        let (left, right, _) = self.usual_arithmetic_conversions(loc, left, right);
+        self.new_comparison(loc, op, left, right)
+    }
+
+    /// Converts a scalar to a bool the way C's `if` does: non-zero numbers
+    /// and non-NULL pointers are true.
+    pub fn new_truth_value<T: ToRValue>(&self, loc: Option<Location>, value: T) -> RValue {
+        promote::truth_value(self, loc, value.to_rvalue())
+    }
+
+    /// Adds `left op right` to the block through `__builtin_add_overflow`,
+    /// `__builtin_sub_overflow` or `__builtin_mul_overflow`, and returns the
+    /// result, wrapped to `result_type`, together with a bool telling whether
+    /// it overflowed. The operands may be any integers; the operation is done
+    /// as if with infinite precision. Panics for other operations or types.
+    pub fn new_overflowing_op<L: ToRValue, R: ToRValue>(
+        &self,
+        block: Block,
+        loc: Option<Location>,
+        op: BinaryOp,
+        left: L,
+        right: R,
+        result_type: types::Type,
+    ) -> (RValue, RValue) {
+        let left = left.to_rvalue();
+        let right = right.to_rvalue();
+        let name = match op {
+            BinaryOp::Plus => "__builtin_add_overflow",
+            BinaryOp::Minus => "__builtin_sub_overflow",
+            BinaryOp::Mult => "__builtin_mul_overflow",
+            _ => panic!("overflow checking is only for +, - and *"),
+        };
+        for ty in &[left.get_type(), right.get_type(), result_type] {
+            if !ty.is_integral() {
+                panic!("overflow checking needs integers, not {:?}", ty);
+            }
+        }
+        let func = block.get_function();
+        let result = func.new_local(loc, result_type, "overflow_result");
+        let overflowed = func.new_local(loc, self.new_type::<bool>(), "overflowed");
+        let builtin = self.get_builtin_function(name);
+        let call = self.new_call(loc, builtin, &[left, right, result.get_address(loc)]);
+        block.add_assignment(loc, overflowed, call);
+        (result.to_rvalue(), overflowed.to_rvalue())
+    }
+
+    /// Like new_overflowing_op, but ends the block by jumping to `on_overflow`
+    /// if the operation overflowed and to `on_success` otherwise. The result
+    /// is returned for use in `on_success`; `on_overflow` would usually trap
+    /// or report the error.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_checked_op<L: ToRValue, R: ToRValue>(
+        &self,
+        block: Block,
+        loc: Option<Location>,
+        op: BinaryOp,
+        left: L,
+        right: R,
+        result_type: types::Type,
+        on_overflow: Block,
+        on_success: Block,
+    ) -> RValue {
+        let (result, overflowed) =
+            self.new_overflowing_op(block, loc, op, left, right, result_type);
+        block.end_with_conditional(loc, overflowed, on_overflow, on_success);
+        result
+    }
+
     /// Creates a function call to a function object with a given number of parameters.
     /// The RValue that is returned is the result of the function call.
     /// Note that due to the way that Rust's generics work, it is currently
@@ -603,6 +927,57 @@ impl Context {
         }
     }
 
+    /// Reinterprets the bits of a value as another type, like a memcpy
+    /// between the two or C++'s std::bit_cast. Panics if the types have
+    /// different sizes.
+    pub fn new_bitcast<T: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        value: T,
+        dest_type: types::Type,
+    ) -> RValue {
+        let rvalue = value.to_rvalue();
+        let src_type = rvalue.get_type();
+        let src_size = layout::size_of(src_type);
+        let dest_size = layout::size_of(dest_type);
+        if src_size != dest_size {
+            panic!(
+                "cannot bitcast {:?} ({} bytes) to {:?} ({} bytes)",
+                src_type, src_size, dest_type, dest_size
+            );
+        }
+        let loc_ptr = match loc {
+            Some(loc) => unsafe { location::get_ptr(&loc) },
+            None => ptr::null_mut(),
+        };
+        unsafe {
+            let ptr = gccjit_sys::gcc_jit_context_new_bitcast(
+                self.ptr,
+                loc_ptr,
+                rvalue::get_ptr(&rvalue),
+                types::get_ptr(&dest_type),
+            );
+            rvalue::from_ptr(ptr)
+        }
+    }
+
+    /// Creates an RValue for C's sizeof(ty). It has type int, and unlike
+    /// layout::size_of it is computed when the code is compiled.
+    pub fn new_sizeof(&self, ty: types::Type) -> RValue {
+        unsafe {
+            let ptr = gccjit_sys::gcc_jit_context_new_sizeof(self.ptr, types::get_ptr(&ty));
+            rvalue::from_ptr(ptr)
+        }
+    }
+
+    /// Creates an RValue for C's _Alignof(ty), of type int.
+    pub fn new_alignof(&self, ty: types::Type) -> RValue {
+        unsafe {
+            let ptr = gccjit_sys::gcc_jit_context_new_alignof(self.ptr, types::get_ptr(&ty));
+            rvalue::from_ptr(ptr)
+        }
+    }
+
     /// Creates an LValue from an array pointer and an offset. The LValue can be the target
     /// of an assignment, or it can be converted into an RValue (i.e. loaded).
     pub fn new_array_access<A: ToRValue, I: ToRValue>(
@@ -652,7 +1027,8 @@ impl Context {
         }
     }
 
-    /// Creates a new RValue from a given double value.
+    /// Creates a floating-point constant. The bits of the double are passed
+    /// on as they are, so infinities, -0.0 and NaN payloads are kept.
     pub fn new_rvalue_from_double(&self, ty: types::Type, value: f64) -> RValue {
         unsafe {
             let ptr = gccjit_sys::gcc_jit_context_new_rvalue_from_double(
@@ -664,6 +1040,269 @@ impl Context {
         }
     }
 
+    /// Creates a constant of an integer type from a u64. Values above
+    /// i64::MAX keep their value, also in types wider than 64 bits, and
+    /// panic for types narrower than 64 bits.
+    pub fn new_rvalue_from_u64(&self, ty: types::Type, value: u64) -> RValue {
+        let u64_ty = self.new_type::<u64>();
+        if value <= i64::MAX as u64 || ty == u64_ty {
+            self.new_rvalue_from_long(ty, value as i64)
+        } else if layout::size_of(ty) >= 8 {
+            self.new_cast(None, self.new_rvalue_from_long(u64_ty, value as i64), ty)
+        } else {
+            panic!("the constant {} doesn't fit in {:?}", value, ty)
+        }
+    }
+
+    /// Creates a constant of an integer type from an i128. libgccjit can
+    /// only take 64-bit constants, so larger values are put together from
+    /// their two halves with a shift and an or, which gcc folds back into
+    /// one constant.
+    pub fn new_rvalue_from_i128(&self, ty: types::Type, value: i128) -> RValue {
+        if value as i64 as i128 == value {
+            return self.new_rvalue_from_long(ty, value as i64);
+        }
+        if value > 0 {
+            return self.new_rvalue_from_u128(ty, value as u128);
+        }
+        let high = self.new_rvalue_from_long(self.new_type::<i64>(), (value >> 64) as i64);
+        self.join_halves(ty, high, value as u64)
+    }
+
+    /// Creates a constant of an integer type from a u128. See
+    /// new_rvalue_from_i128.
+    pub fn new_rvalue_from_u128(&self, ty: types::Type, value: u128) -> RValue {
+        if value as u64 as u128 == value {
+            return self.new_rvalue_from_u64(ty, value as u64);
+        }
+        let u64_ty = self.new_type::<u64>();
+        let high = self.new_rvalue_from_long(u64_ty, (value >> 64) as i64);
+        self.join_halves(ty, high, value as u64)
+    }
+
+    /// Returns `(ty)high << 64 | (ty)low`. Panics unless ty is a 128-bit
+    /// type, as the value wouldn't fit in anything smaller.
+    fn join_halves(&self, ty: types::Type, high: RValue, low: u64) -> RValue {
+        assert_eq!(
+            layout::size_of(ty),
+            16,
+            "a constant wider than 64 bits needs a 128-bit type, not {:?}",
+            ty
+        );
+        let u64_ty = self.new_type::<u64>();
+        let high = self.new_cast(None, high, ty);
+        let low = self.new_cast(None, self.new_rvalue_from_long(u64_ty, low as i64), ty);
+        let shift = self.new_rvalue_from_int(ty, 64);
+        let high = self.new_binary_op(None, BinaryOp::LShift, ty, high, shift);
+        self.new_binary_op(None, BinaryOp::BitwiseOr, ty, high, low)
+    }
+
+    /// Creates a floating-point constant from an f32. Every f32 is exact as
+    /// a double, so this only differs from new_rvalue_from_double for NaNs:
+    /// a NaN stored in a float keeps its exact bits, payload included.
+    pub fn new_rvalue_from_f32(&self, ty: types::Type, value: f32) -> RValue {
+        if value.is_nan() && ty == self.new_type::<f32>() {
+            let bits = self.new_rvalue_from_long(self.new_type::<u32>(), value.to_bits() as i64);
+            self.new_bitcast(None, bits, ty)
+        } else {
+            self.new_rvalue_from_double(ty, value as f64)
+        }
+    }
+
+    /// Creates a constant of T's type from a Rust value, e.g.
+    /// `ctx.new_const(u64::MAX)` or `ctx.new_const(f32::NEG_INFINITY)`.
+    pub fn new_const<T: IntoConst + types::Typeable>(&self, value: T) -> RValue {
+        value.into_const(self, T::get_type(self))
+    }
+
+    /// Creates a complex RValue of the given complex type from its real and
+    /// imaginary parts, which are converted to the component type first.
+    /// Unlike `re + im * I` in C, this is exact for infinities, NaNs and
+    /// negative zeros.
+    pub fn new_complex<R: ToRValue, I: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        ty: types::Type,
+        re: R,
+        im: I,
+    ) -> RValue {
+        let pun = self.complex_pun(ty);
+        let re = self.new_cast(loc, re, pun.part_ty);
+        let im = self.new_cast(loc, im, pun.part_ty);
+        let loc_ptr = match loc {
+            Some(loc) => unsafe { location::get_ptr(&loc) },
+            None => ptr::null_mut(),
+        };
+        unsafe {
+            let mut fields = [field::get_ptr(&pun.re), field::get_ptr(&pun.im)];
+            let mut values = [rvalue::get_ptr(&re), rvalue::get_ptr(&im)];
+            let parts = gcc_jit_context_new_struct_constructor(
+                self.ptr,
+                loc_ptr,
+                types::get_ptr(&pun.pair_ty),
+                2,
+                fields.as_mut_ptr(),
+                values.as_mut_ptr(),
+            );
+            let union = gcc_jit_context_new_union_constructor(
+                self.ptr,
+                loc_ptr,
+                types::get_ptr(&pun.union_ty),
+                field::get_ptr(&pun.parts),
+                parts,
+            );
+            rvalue::from_ptr(union).access_field(loc, pun.value)
+        }
+    }
+
+    /// Creates a struct value from (field, value) pairs, like C's
+    /// `(struct s){ .a = 1, .c = 3 }`. The pairs may come in any order and
+    /// fields that are left out are zero. If every value is constant, so is
+    /// the result, and it can be used to initialize a global.
+    pub fn new_struct_constructor(
+        &self,
+        loc: Option<Location>,
+        ty: types::Type,
+        values: &[(Field, RValue)],
+    ) -> RValue {
+        let key = |f: &Field| unsafe { field::get_ptr(f) };
+        let mut values = values.to_vec();
+        if let Some(members) = cdecl::members(ty) {
+            for (f, _) in &values {
+                if !members.iter().any(|m| key(m) == key(f)) {
+                    panic!("{:?} is not a field of {:?}", f, ty);
+                }
+            }
+            // gccjit wants the fields in declaration order.
+            values.sort_by_key(|(f, _)| members.iter().position(|m| key(m) == key(f)));
+        }
+        let mut fields: Vec<_> = values.iter().map(|(f, _)| key(f)).collect();
+        let mut values: Vec<_> = values
+            .iter()
+            .map(|(_, v)| unsafe { rvalue::get_ptr(v) })
+            .collect();
+        let loc_ptr = match loc {
+            Some(loc) => unsafe { location::get_ptr(&loc) },
+            None => ptr::null_mut(),
+        };
+        unsafe {
+            let ptr = gcc_jit_context_new_struct_constructor(
+                self.ptr,
+                loc_ptr,
+                types::get_ptr(&ty),
+                values.len(),
+                fields.as_mut_ptr(),
+                values.as_mut_ptr(),
+            );
+            rvalue::from_ptr(ptr)
+        }
+    }
+
+    /// Creates an array value from its first elements, like C's
+    /// `(int[4]){ 1, 2 }`. Elements past the end of `values` are zero. If
+    /// every value is constant, so is the result.
+    pub fn new_array_constructor(
+        &self,
+        loc: Option<Location>,
+        ty: types::Type,
+        values: &[RValue],
+    ) -> RValue {
+        let mut values: Vec<_> = values
+            .iter()
+            .map(|v| unsafe { rvalue::get_ptr(v) })
+            .collect();
+        let loc_ptr = match loc {
+            Some(loc) => unsafe { location::get_ptr(&loc) },
+            None => ptr::null_mut(),
+        };
+        unsafe {
+            let ptr = gcc_jit_context_new_array_constructor(
+                self.ptr,
+                loc_ptr,
+                types::get_ptr(&ty),
+                values.len(),
+                values.as_mut_ptr(),
+            );
+            rvalue::from_ptr(ptr)
+        }
+    }
+
+    /// Creates a union value with one field set, like C's
+    /// `(union u){ .f = value }`. Passing None gives a zeroed union.
+    pub fn new_union_constructor(
+        &self,
+        loc: Option<Location>,
+        ty: types::Type,
+        value: Option<(Field, RValue)>,
+    ) -> RValue {
+        let (field_ptr, value_ptr) = match value {
+            Some((f, v)) => unsafe { (field::get_ptr(&f), rvalue::get_ptr(&v)) },
+            None => (ptr::null_mut(), ptr::null_mut()),
+        };
+        let loc_ptr = match loc {
+            Some(loc) => unsafe { location::get_ptr(&loc) },
+            None => ptr::null_mut(),
+        };
+        unsafe {
+            let ptr = gcc_jit_context_new_union_constructor(
+                self.ptr,
+                loc_ptr,
+                types::get_ptr(&ty),
+                field_ptr,
+                value_ptr,
+            );
+            rvalue::from_ptr(ptr)
+        }
+    }
+
+    /// Creates a constant of the given complex type.
+    pub fn new_rvalue_from_complex(&self, ty: types::Type, value: types::Complex<f64>) -> RValue {
+        let part_ty = self.complex_pun(ty).part_ty;
+        self.new_complex(
+            None,
+            ty,
+            self.new_rvalue_from_double(part_ty, value.re),
+            self.new_rvalue_from_double(part_ty, value.im),
+        )
+    }
+
+    /// Returns `union { struct { T re, im; } parts; _Complex T value; }` for
+    /// a complex type, which is how complex values are put together.
+    fn complex_pun(&self, ty: types::Type) -> ComplexPun {
+        let puns = context_state::<ComplexPuns>(self);
+        if let Some(&pun) = puns.borrow().0.get(&ty) {
+            return pun;
+        }
+        let part_ty = if ty == self.new_type::<types::Complex<f32>>() {
+            self.new_type::<f32>()
+        } else if ty == self.new_type::<types::Complex<f64>>() {
+            self.new_type::<f64>()
+        } else if ty == types::Type::complex_long_double(self) {
+            types::Type::from_const(self, gcc_jit_types_GCC_JIT_TYPE_LONG_DOUBLE)
+        } else {
+            panic!("{:?} is not a complex type", ty)
+        };
+        let re = self.new_field(None, part_ty, "re");
+        let im = self.new_field(None, part_ty, "im");
+        let pair_ty = self.new_struct_type(None, "complex_parts", &[re, im]).as_type();
+        let parts = self.new_field(None, pair_ty, "parts");
+        let value = self.new_field(None, ty, "value");
+        let union_ty = self
+            .new_union_type(None, "complex_pun", &[parts, value])
+            .as_type();
+        let pun = ComplexPun {
+            part_ty,
+            pair_ty,
+            re,
+            im,
+            union_ty,
+            parts,
+            value,
+        };
+        puns.borrow_mut().0.insert(ty, pun);
+        pun
+    }
+
     /// Creates a zero element for a given type.
     pub fn new_rvalue_zero(&self, ty: types::Type) -> RValue {
         unsafe {
@@ -711,6 +1350,58 @@ impl Context {
         }
     }
 
+    /// Creates a constant `unsigned char[N]` holding arbitrary bytes,
+    /// including NULs. With `nul_terminated` an extra 0 is appended, like
+    /// in a C string literal. The result is an array value, so it can
+    /// initialize a global or a local; take the address of that to get a
+    /// pointer.
+    ///
+    /// Each byte becomes a constant of its own in the context, so this is
+    /// meant for short strings. For a blob of any real size, use
+    /// new_byte_string_global, which copies the bytes in one go.
+    pub fn new_byte_string_literal(&self, value: &[u8], nul_terminated: bool) -> RValue {
+        let byte = self.new_type::<u8>();
+        let len = value.len() + nul_terminated as usize;
+        let len = match i32::try_from(len) {
+            Ok(len) => len,
+            Err(_) => panic!("gccjit arrays can't have {} elements", len),
+        };
+        let ty = self.new_array_type(None, byte, len);
+        let bytes: Vec<_> = value
+            .iter()
+            .map(|&b| self.new_rvalue_from_int(byte, b as i32))
+            .collect();
+        // The terminator, if any, is the zero that fills the rest of the array.
+        self.new_array_constructor(None, ty, &bytes)
+    }
+
+    /// Creates a global `unsigned char name[N]` initialized with arbitrary
+    /// bytes, with an extra 0 appended if `nul_terminated`. Unlike
+    /// new_byte_string_literal, the bytes are handed to libgccjit as one
+    /// buffer, which it copies, so the cost doesn't grow with a constant per
+    /// byte.
+    pub fn new_byte_string_global(
+        &self,
+        loc: Option<Location>,
+        kind: GlobalKind,
+        name: &str,
+        value: &[u8],
+        nul_terminated: bool,
+    ) -> LValue {
+        let mut bytes = value.to_vec();
+        if nul_terminated {
+            bytes.push(0);
+        }
+        let len = match i32::try_from(bytes.len()) {
+            Ok(len) => len,
+            Err(_) => panic!("gccjit arrays can't have {} elements", bytes.len()),
+        };
+        let ty = self.new_array_type(loc, self.new_type::<u8>(), len);
+        let global = self.new_global(loc, kind, ty, name);
+        global.set_initializer(&bytes);
+        global
+    }
+
     /// Dumps a small C file to the path that can be used to reproduce a series
     /// of API calls. You should only ever need to call this if you are debugging
     /// an issue in gccjit itself or this library.
@@ -742,7 +1433,9 @@ impl Context {
                 types::get_ptr(&ty),
                 cstr.as_ptr(),
             );
-            parameter::from_ptr(ptr)
+            let param = parameter::from_ptr(ptr);
+            cdecl::record_param(param, ty);
+            param
         }
     }
 
@@ -757,12 +1450,74 @@ impl Context {
             function::from_ptr(ptr)
         }
     }
+
+    /// Sets the block that compound assignments on LValues, such as
+    /// `x += 1`, are added to. None clears it.
+    pub fn set_current_block(&self, block: Option<Block>) {
+        context_state::<CurrentBlock>(self).borrow_mut().0 = block;
+    }
+
+    /// Gets the block set by set_current_block, if any.
+    pub fn current_block(&self) -> Option<Block> {
+        context_state::<CurrentBlock>(self).borrow().0
+    }
+}
+
+/// The block set by set_current_block.
+#[derive(Default)]
+struct CurrentBlock(Option<Block>);
+
+/// The puns made by complex_pun, by complex type.
+#[derive(Default)]
+struct ComplexPuns(HashMap<types::Type, ComplexPun>);
+
+#[derive(Copy, Clone)]
+struct ComplexPun {
+    part_ty: types::Type,
+    pair_ty: types::Type,
+    re: Field,
+    im: Field,
+    union_ty: types::Type,
+    parts: Field,
+    value: Field,
+}
+
+thread_local! {
+    /// What this crate keeps about each context on top of what libgccjit
+    /// keeps, by context and then by the type of the state.
+    static STATES: RefCell<HashMap<usize, HashMap<TypeId, Rc<dyn Any>>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Gets the state of type T that this crate keeps for a context, creating
+/// it on first use. It is dropped by Context::release, so tables keyed by
+/// the addresses of objects made in the context never outlive the objects.
+/// States are told apart by type, so each one should be a type private to
+/// the module that keeps it.
+pub(crate) fn context_state<T: Default + 'static>(ctx: &Context) -> Rc<RefCell<T>> {
+    let state = STATES.with(|states| {
+        states
+            .borrow_mut()
+            .entry(ctx.ptr as usize)
+            .or_insert_with(HashMap::new)
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Rc::new(RefCell::new(T::default())))
+            .clone()
+    });
+    match state.downcast::<RefCell<T>>() {
+        Ok(state) => state,
+        Err(_) => unreachable!("context state is keyed by its type"),
+    }
 }
 
 pub fn context_get_ptr(ctx: &Context) -> *mut gcc_jit_context {
     ctx.ptr
 }
 
+pub unsafe fn context_from_ptr(ptr: *mut gcc_jit_context) -> Context {
+    Context { ptr }
+}
+
 pub struct CompileResult {
     ptr: *mut gccjit_sys::gcc_jit_result,
 }
@@ -785,7 +1540,8 @@ impl CompileResult {
         }
     }
 
-    /// Gets a pointer to a global variable that lives on the JIT heap.
+    /// Gets a pointer to a global variable that lives on the JIT heap. For
+    /// a thread-local global, this is the calling thread's copy.
     ///
     /// It is the caller's responsibility
     /// to ensure that the pointer is not used past the lifetime of the
@@ -800,6 +1556,14 @@ impl CompileResult {
     }
 }
 
+// A gcc_jit_result is only a handle to the loaded code: nothing changes it
+// after compilation, and get_code and get_global are plain dlsym lookups,
+// which may run on any thread. Releasing it takes ownership, in Drop, so it
+// can't race with a lookup. Sharing results between threads is what makes
+// thread-local globals usable, as get_global gives the calling thread's copy.
+unsafe impl Send for CompileResult {}
+unsafe impl Sync for CompileResult {}
+
 impl Drop for CompileResult {
     fn drop(&mut self) {
         unsafe {
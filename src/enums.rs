@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::block::{Block, Case};
+use crate::ctx::{self, Context};
+use crate::layout;
+use crate::object::ToObject;
+use crate::rvalue::{self, RValue};
+use crate::ty::Type;
+
+/// An EnumType is a C-style enum: a name and a set of named constants of
+/// an underlying integer type. gccjit has no enums of its own, so values of
+/// an EnumType are plain values of the underlying type, and the variant
+/// names only live on this side: the constants made by `variant` show
+/// their name when formatted with Debug, but gccjit's own dumps and the
+/// debug strings of expressions using them show the number.
+///
+/// Values are i128 so that every value of every underlying type up to 64
+/// bits, signed or not, can be given, as can most 128-bit ones.
+#[derive(Clone)]
+pub struct EnumType {
+    ctx: Context,
+    name: String,
+    ty: Type,
+    variants: Vec<(String, i128)>,
+}
+
+impl EnumType {
+    /// Gets the underlying integer type, which is what values of this enum
+    /// have in gccjit.
+    pub fn as_type(&self) -> Type {
+        self.ty
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the variant names and values, in declaration order.
+    pub fn variants(&self) -> &[(String, i128)] {
+        &self.variants
+    }
+
+    /// Gets the value of the named variant, if there is one.
+    pub fn value_of(&self, variant: &str) -> Option<i128> {
+        self.variants
+            .iter()
+            .find(|(name, _)| name == variant)
+            .map(|&(_, value)| value)
+    }
+
+    /// Gets the name of the first variant with the given value, if there is one.
+    pub fn name_of(&self, value: i128) -> Option<&str> {
+        self.variants
+            .iter()
+            .find(|&&(_, v)| v == value)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Creates a constant for the named variant. Panics if the enum has no
+    /// such variant.
+    pub fn variant(&self, variant: &str) -> RValue {
+        let value = self.expect(variant);
+        let constant = self.ctx.new_rvalue_from_i128(self.ty, value);
+        let key = unsafe { rvalue::get_ptr(&constant) } as usize;
+        let names = ctx::context_state::<VariantNames>(&self.ctx);
+        names.borrow_mut().0.insert(key, variant.to_string());
+        constant
+    }
+
+    /// Creates a switch case that jumps to `dest_block` when the switched
+    /// on value is the named variant.
+    pub fn new_case(&self, variant: &str, dest_block: Block) -> Case {
+        let value = self.variant(variant);
+        self.ctx.new_case(value, value, dest_block)
+    }
+
+    fn expect(&self, variant: &str) -> i128 {
+        match self.value_of(variant) {
+            Some(value) => value,
+            None => panic!("enum {} has no variant named {}", self.name, variant),
+        }
+    }
+}
+
+impl fmt::Debug for EnumType {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "enum {} : {:?} {{ ", self.name, self.ty)?;
+        for (i, (name, value)) in self.variants.iter().enumerate() {
+            if i > 0 {
+                fmt.write_str(", ")?;
+            }
+            write!(fmt, "{} = {}", name, value)?;
+        }
+        fmt.write_str(" }")
+    }
+}
+
+/// The names of the constants made by EnumType::variant, by address.
+#[derive(Default)]
+struct VariantNames(HashMap<usize, String>);
+
+/// Gets the variant name of a constant made by EnumType::variant.
+pub(crate) fn variant_name(value: RValue) -> Option<String> {
+    let ctx = value.to_object().get_context();
+    let key = unsafe { rvalue::get_ptr(&value) } as usize;
+    let names = ctx::context_state::<VariantNames>(&ctx);
+    let name = names.borrow().0.get(&key).cloned();
+    name
+}
+
+/// Checks the variants and puts the EnumType together. Every value must fit
+/// in the underlying type, and names must be unique.
+pub(crate) fn new(ctx: Context, name: &str, ty: Type, variants: &[(&str, i128)]) -> EnumType {
+    if !ty.is_integral() {
+        panic!("enum {} has non-integer underlying type {:?}", name, ty);
+    }
+    let bits = layout::size_of(ty) as u32 * 8;
+    let (min, max) = if ty.is_signed() {
+        (i128::MIN >> (128 - bits), i128::MAX >> (128 - bits))
+    } else {
+        let max = u128::MAX >> (128 - bits);
+        (0, i128::try_from(max).unwrap_or(i128::MAX))
+    };
+    for (i, &(variant, value)) in variants.iter().enumerate() {
+        if value < min || value > max {
+            panic!(
+                "value {} of {}::{} does not fit in {:?}",
+                value, name, variant, ty
+            );
+        }
+        if variants[..i].iter().any(|&(other, _)| other == variant) {
+            panic!("enum {} has more than one variant named {}", name, variant);
+        }
+    }
+    EnumType {
+        ctx,
+        name: name.to_string(),
+        ty,
+        variants: variants
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value))
+            .collect(),
+    }
+}
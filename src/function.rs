@@ -1,6 +1,6 @@
 use crate::block;
 use crate::block::Block;
-use crate::ctx::Context;
+use crate::ctx::{Context, Visibility};
 use crate::location;
 use crate::location::Location;
 use crate::lvalue;
@@ -23,6 +23,7 @@ use std::ptr;
 /// a function that cannot be called outside of jitted code. An extern function
 /// is a function with external linkage, and always inline is a function that is
 /// always inlined wherever it is called and cannot be accessed outside of the jit.
+#[derive(Copy, Clone, Debug)]
 #[repr(C)]
 pub enum FunctionType {
     /// Defines a function that is "exported" by the JIT and can be called from
@@ -72,6 +73,11 @@ impl Function {
         }
     }
 
+    /// Gets the type the function returns.
+    pub fn get_return_type(&self) -> Type {
+        unsafe { types::from_ptr(gccjit_sys::gcc_jit_function_get_return_type(self.ptr)) }
+    }
+
     pub fn get_address(&self, loc: Option<Location>) -> crate::rvalue::RValue {
         unsafe {
             crate::rvalue::from_ptr(gccjit_sys::gcc_jit_function_get_address(
@@ -112,6 +118,21 @@ impl Function {
             lvalue::from_ptr(ptr)
         }
     }
+
+    /// Sets the visibility of a function's symbol. Hidden functions can be
+    /// called from the rest of the object file, but aren't exported from it.
+    ///
+    /// There is no set_link_section for functions: libgccjit only supports
+    /// the `section` attribute on globals, see LValue::set_link_section.
+    pub fn set_visibility(&self, visibility: Visibility) {
+        unsafe {
+            gccjit_sys::gcc_jit_function_add_string_attribute(
+                self.ptr,
+                gccjit_sys::gcc_jit_fn_attribute_GCC_JIT_FN_ATTRIBUTE_VISIBILITY,
+                visibility.as_cstr().as_ptr(),
+            );
+        }
+    }
 }
 
 pub unsafe fn from_ptr(ptr: *mut gccjit_sys::gcc_jit_function) -> Function {
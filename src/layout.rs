@@ -0,0 +1,588 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::ptr;
+
+use crate::cdecl::{self, Shape};
+use crate::ctx::{self, Context};
+use crate::field::{self, Field};
+use crate::function::FunctionType;
+use crate::lvalue::LValue;
+use crate::object::ToObject;
+use crate::rvalue::{self, RValue, ToRValue};
+use crate::structs::Struct;
+use crate::sys::*;
+use crate::ty::{self as types, Complex, Type};
+
+/// The size and alignment of a type, in bytes, as gccjit lays it out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub size: usize,
+    pub align: usize,
+}
+
+/// The size, alignment and field offsets of a struct, in bytes. The
+/// offsets are in the same order as the fields that were asked for.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructLayout {
+    pub size: usize,
+    pub align: usize,
+    pub offsets: Vec<usize>,
+}
+
+/// Returns the size and alignment of a type, as gccjit lays it out.
+///
+/// Integers are measured with `gcc_jit_type_get_size`. For everything else
+/// the type is rebuilt in a separate context, which compiles a probe
+/// function returning its sizeof, alignof and field offsets. Probes are
+/// cached: basic types are measured once per thread, other types once per
+/// context. The context being built is never compiled or otherwise
+/// touched, so this can be called at any point, e.g. with a block open.
+///
+/// Pointers are all measured as `void *`. A type that can't be rebuilt,
+/// because it wasn't made by this crate, or whose probe fails to compile
+/// falls back to a model of the C rules for the target in Rust, which
+/// can't see e.g. an alignment set through raw gccjit calls. Panics for
+/// incomplete types, such as void or an opaque struct.
+pub fn type_layout(ty: Type) -> Layout {
+    measure(ty).layout
+}
+
+/// Returns the size of a type. See `type_layout`.
+pub fn size_of(ty: Type) -> usize {
+    let base = ty.unqualified();
+    if base.is_integral() {
+        return unsafe { gcc_jit_type_get_size(types::get_ptr(&base)) as usize };
+    }
+    type_layout(ty).size
+}
+
+/// Returns the alignment of a type. See `type_layout`.
+pub fn align_of(ty: Type) -> usize {
+    type_layout(ty).align
+}
+
+/// Returns the size, alignment and offsets of the given fields of a struct.
+/// The fields must be ones the struct was created with, and can't be
+/// bit-fields since those don't start at a byte.
+pub fn struct_layout(s: Struct, fields: &[Field]) -> StructLayout {
+    let ty = s.as_type();
+    let layout = type_layout(ty);
+    StructLayout {
+        size: layout.size,
+        align: layout.align,
+        offsets: field_offsets(ty, fields),
+    }
+}
+
+/// Checks that a gccjit struct is laid out exactly like the Rust type T.
+/// Each field is paired with the offset of the corresponding Rust field,
+/// usually obtained from `std::mem::offset_of!`; the `assert_layout!` macro
+/// does this pairing for you. Panics with a description of every mismatch.
+pub fn assert_struct_layout<T>(s: Struct, fields: &[(Field, usize)]) {
+    let just_fields: Vec<_> = fields.iter().map(|&(f, _)| f).collect();
+    let layout = struct_layout(s, &just_fields);
+    let mut mismatches = vec![];
+    if layout.size != mem::size_of::<T>() {
+        mismatches.push(format!(
+            "size is {} in gccjit but {} in Rust",
+            layout.size,
+            mem::size_of::<T>()
+        ));
+    }
+    if layout.align != mem::align_of::<T>() {
+        mismatches.push(format!(
+            "alignment is {} in gccjit but {} in Rust",
+            layout.align,
+            mem::align_of::<T>()
+        ));
+    }
+    for (&(field, rust_offset), &offset) in fields.iter().zip(layout.offsets.iter()) {
+        if offset != rust_offset {
+            mismatches.push(format!(
+                "field {:?} is at offset {} in gccjit but {} in Rust",
+                field, offset, rust_offset
+            ));
+        }
+    }
+    if !mismatches.is_empty() {
+        panic!(
+            "layout of {:?} does not match {}:\n  {}",
+            s,
+            std::any::type_name::<T>(),
+            mismatches.join("\n  ")
+        );
+    }
+}
+
+/// Checks that a gccjit struct has the same size, alignment and field
+/// offsets as a Rust type:
+///
+/// ```ignore
+/// assert_layout!(Point, point_struct, { x: x_field, y: y_field });
+/// ```
+#[macro_export]
+macro_rules! assert_layout {
+    ($ty:ty, $s:expr, { $($name:ident : $field:expr),* $(,)? }) => {
+        $crate::layout::assert_struct_layout::<$ty>(
+            $s,
+            &[$(($field, ::std::mem::offset_of!($ty, $name))),*],
+        )
+    };
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "size {}, align {}", self.size, self.align)
+    }
+}
+
+/// What a probe found out about a type: its layout and, for a struct or
+/// union, the byte offset of each of its fields, None for bit-fields.
+#[derive(Clone)]
+struct Measurement {
+    layout: Layout,
+    offsets: Vec<Option<usize>>,
+}
+
+/// The measurements of the types of a context.
+#[derive(Default)]
+struct Measured(HashMap<Type, Measurement>);
+
+thread_local! {
+    /// The layouts of the basic types, by kind. They only depend on the
+    /// target, so they are shared by every context.
+    static BASIC_LAYOUTS: RefCell<HashMap<u32, Layout>> = RefCell::new(HashMap::new());
+}
+
+fn measure(ty: Type) -> Measurement {
+    let ctx = ty.to_object().get_context();
+    let measured = ctx::context_state::<Measured>(&ctx);
+    if let Some(measurement) = measured.borrow().0.get(&ty) {
+        return measurement.clone();
+    }
+    // Pointers are measured as void *, see Rebuild::build.
+    let kind = if ty.is_pointer() {
+        Some(gcc_jit_types_GCC_JIT_TYPE_VOID_PTR)
+    } else {
+        basic_kind(&ctx, ty)
+    };
+    let cached =
+        kind.and_then(|kind| BASIC_LAYOUTS.with(|layouts| layouts.borrow().get(&kind).copied()));
+    let measurement = match cached {
+        Some(layout) => Measurement {
+            layout,
+            offsets: Vec::new(),
+        },
+        None => {
+            let measurement = probe(ty).unwrap_or_else(|| model(ty));
+            if let Some(kind) = kind {
+                BASIC_LAYOUTS.with(|layouts| layouts.borrow_mut().insert(kind, measurement.layout));
+            }
+            measurement
+        }
+    };
+    measured.borrow_mut().0.insert(ty, measurement.clone());
+    measurement
+}
+
+/// The kinds of basic types that have a size, i.e. all but void.
+const BASIC_KINDS: std::ops::RangeInclusive<u32> =
+    gcc_jit_types_GCC_JIT_TYPE_VOID_PTR..=gcc_jit_types_GCC_JIT_TYPE_INT128_T;
+
+/// Returns the kind of a basic type, such as GCC_JIT_TYPE_INT, or None if
+/// `ty` is not one.
+fn basic_kind(ctx: &Context, ty: Type) -> Option<u32> {
+    BASIC_KINDS
+        .clone()
+        .find(|&kind| ty == Type::from_const(ctx, kind))
+}
+
+/// Measures `ty` by rebuilding it in a new context and compiling
+///
+/// ```c
+/// void probe(size_t *out) {
+///     T value;
+///     out[0] = sizeof(T);
+///     out[1] = _Alignof(T);
+///     out[2 + i] = (size_t)&value.field_i - (size_t)&value;
+/// }
+/// ```
+///
+/// with a line for each field that isn't a bit-field. Returns None if the
+/// type can't be rebuilt or the probe doesn't compile.
+fn probe(ty: Type) -> Option<Measurement> {
+    let probe_ctx = Context::default();
+    let measurement = run_probe(&probe_ctx, ty);
+    // Safety: nothing made in the probe context escapes this function.
+    unsafe { probe_ctx.release() };
+    measurement
+}
+
+fn run_probe(probe_ctx: &Context, ty: Type) -> Option<Measurement> {
+    let mut rebuild = Rebuild {
+        ctx: probe_ctx,
+        types: HashMap::new(),
+        fields: HashMap::new(),
+    };
+    let probe_ty = rebuild.ty(ty)?;
+    let members = cdecl::members(ty).unwrap_or_default();
+    let probe_members: Vec<_> = members
+        .iter()
+        .map(|field| rebuild.fields[&key(*field)])
+        .collect();
+
+    let size_t = probe_ctx.new_type::<usize>();
+    let out_param = probe_ctx.new_parameter(None, size_t.make_pointer(), "out");
+    let func = probe_ctx.new_function(
+        None,
+        FunctionType::Exported,
+        probe_ctx.new_type::<()>(),
+        &[out_param],
+        "probe",
+        false,
+    );
+    let out = func.get_param(0).to_rvalue();
+    let block = func.new_block("entry");
+    let set = |index: usize, value: RValue| {
+        let index = probe_ctx.new_rvalue_from_long(size_t, index as i64);
+        let value = probe_ctx.new_cast(None, value, size_t);
+        block.add_assignment(None, probe_ctx.new_array_access(None, out, index), value);
+    };
+    set(0, probe_ctx.new_sizeof(probe_ty));
+    set(1, probe_ctx.new_alignof(probe_ty));
+    let value = func.new_local(None, probe_ty, "value");
+    let address = |lvalue: LValue| unsafe {
+        let address = lvalue.get_address(None);
+        rvalue::from_ptr(gcc_jit_context_new_bitcast(
+            ctx::context_get_ptr(probe_ctx),
+            ptr::null_mut(),
+            rvalue::get_ptr(&address),
+            types::get_ptr(&size_t),
+        ))
+    };
+    let base = address(value);
+    let mut byte_fields = Vec::new();
+    for (index, (&member, &field)) in members.iter().zip(probe_members.iter()).enumerate() {
+        if let Some((_, Some(_))) = cdecl::field_type(member) {
+            continue;
+        }
+        let offset = address(value.access_field(None, field)) - base;
+        set(2 + byte_fields.len(), offset);
+        byte_fields.push(index);
+    }
+    block.end_with_void_return(None);
+
+    let result = probe_ctx.compile();
+    if probe_ctx.get_first_error().is_some() {
+        return None;
+    }
+    let probe_fn = result.get_function("probe");
+    if probe_fn.is_null() {
+        return None;
+    }
+    let probe_fn: extern "C" fn(*mut usize) = unsafe { mem::transmute(probe_fn) };
+    let mut out = vec![0; 2 + byte_fields.len()];
+    probe_fn(out.as_mut_ptr());
+
+    let mut offsets = vec![None; members.len()];
+    for (slot, &index) in byte_fields.iter().enumerate() {
+        offsets[index] = Some(out[2 + slot]);
+    }
+    Some(Measurement {
+        layout: Layout {
+            size: out[0],
+            align: out[1],
+        },
+        offsets,
+    })
+}
+
+/// Copies a type into the probe context, from what was recorded about how
+/// it was built.
+struct Rebuild<'a> {
+    ctx: &'a Context,
+    types: HashMap<Type, Type>,
+    fields: HashMap<usize, Field>,
+}
+
+impl<'a> Rebuild<'a> {
+    fn ty(&mut self, ty: Type) -> Option<Type> {
+        if let Some(&rebuilt) = self.types.get(&ty) {
+            return Some(rebuilt);
+        }
+        let rebuilt = self.build(ty)?;
+        self.types.insert(ty, rebuilt);
+        Some(rebuilt)
+    }
+
+    fn build(&mut self, ty: Type) -> Option<Type> {
+        let ctx = ty.to_object().get_context();
+        // Every pointer has the size and alignment of void *, so pointers
+        // never have to follow their pointee, e.g. back into a recursive
+        // struct.
+        if ty.is_pointer() {
+            return Some(self.ctx.new_type::<()>().make_pointer());
+        }
+        if let Some(kind) = basic_kind(&ctx, ty) {
+            return Some(Type::from_const(self.ctx, kind));
+        }
+        match cdecl::shape(ty) {
+            Some(Shape::Const(inner)) => return Some(self.ty(inner)?.make_const()),
+            Some(Shape::Volatile(inner)) => return Some(self.ty(inner)?.make_volatile()),
+            Some(Shape::Array(element, len)) => {
+                let element = self.ty(element)?;
+                return Some(self.ctx.new_array_type(None, element, len));
+            }
+            _ => {}
+        }
+        let vector = unsafe { gcc_jit_type_dyncast_vector(types::get_ptr(&ty)) };
+        if !vector.is_null() {
+            let (element, units) = unsafe {
+                (
+                    types::from_ptr(gcc_jit_vector_type_get_element_type(vector)),
+                    gcc_jit_vector_type_get_num_units(vector),
+                )
+            };
+            let element = self.ty(element)?;
+            return Some(unsafe {
+                types::from_ptr(gcc_jit_type_get_vector(types::get_ptr(&element), units))
+            });
+        }
+        let members = cdecl::members(ty)?;
+        let mut fields = Vec::with_capacity(members.len());
+        for member in members {
+            let (field_ty, width) = cdecl::field_type(member)?;
+            let field_ty = self.ty(field_ty)?;
+            let name = cdecl::field_name(member)?;
+            let field = match width {
+                Some(width) => self.ctx.new_bitfield(None, field_ty, width, name),
+                None => self.ctx.new_field(None, field_ty, name),
+            };
+            self.fields.insert(key(member), field);
+            fields.push(field);
+        }
+        let name = format!("probed{}", self.types.len());
+        Some(if cdecl::is_union(ty) {
+            self.ctx.new_union_type(None, name, &fields).as_type()
+        } else {
+            self.ctx.new_struct_type(None, name, &fields).as_type()
+        })
+    }
+}
+
+fn key(field: Field) -> usize {
+    unsafe { field::get_ptr(&field) as usize }
+}
+
+/// The fallback for types that can't be probed: the C rules for the
+/// target, spelled out in Rust.
+fn model(ty: Type) -> Measurement {
+    match cdecl::shape(ty) {
+        Some(Shape::Const(inner)) | Some(Shape::Volatile(inner)) => return measure(inner),
+        Some(Shape::Array(element, len)) => {
+            let element = type_layout(element);
+            return Measurement {
+                layout: Layout {
+                    size: element.size * len as usize,
+                    align: element.align,
+                },
+                offsets: Vec::new(),
+            };
+        }
+        _ => {}
+    }
+    if ty.unqualified() != ty {
+        panic!(
+            "cannot compute the layout of {:?}: its qualifiers or alignment weren't set by this crate",
+            ty
+        );
+    }
+    let layout = |layout| Measurement {
+        layout,
+        offsets: Vec::new(),
+    };
+    if ty.is_pointer() {
+        return layout(layout_of::<usize>());
+    }
+    if let Some(scalar) = scalar_layout(ty) {
+        return layout(scalar);
+    }
+    let vector = unsafe { gcc_jit_type_dyncast_vector(types::get_ptr(&ty)) };
+    if !vector.is_null() {
+        // GCC aligns vectors to their size.
+        let (element, units) = unsafe {
+            (
+                types::from_ptr(gcc_jit_vector_type_get_element_type(vector)),
+                gcc_jit_vector_type_get_num_units(vector),
+            )
+        };
+        let size = type_layout(element).size * units;
+        return layout(Layout { size, align: size });
+    }
+    match cdecl::members(ty) {
+        Some(fields) => {
+            let (record, bit_offsets) = record_layout(ty, &fields);
+            let offsets = fields
+                .iter()
+                .zip(bit_offsets)
+                .map(|(&field, offset)| match cdecl::field_type(field) {
+                    Some((_, Some(_))) => None,
+                    _ => Some(offset / 8),
+                })
+                .collect();
+            Measurement {
+                layout: record,
+                offsets,
+            }
+        }
+        None => panic!(
+            "cannot compute the layout of {:?}: it is incomplete, or wasn't built by this crate",
+            ty
+        ),
+    }
+}
+
+fn layout_of<T>() -> Layout {
+    Layout {
+        size: mem::size_of::<T>(),
+        align: mem::align_of::<T>(),
+    }
+}
+
+/// The layout of the basic types other than pointers, or None if `ty`
+/// isn't one.
+fn scalar_layout(ty: Type) -> Option<Layout> {
+    let ctx = ty.to_object().get_context();
+    unsafe {
+        if gcc_jit_type_is_bool(types::get_ptr(&ty)) != 0 {
+            return Some(layout_of::<bool>());
+        }
+        if gcc_jit_type_is_integral(types::get_ptr(&ty)) != 0 {
+            let size = gcc_jit_type_get_size(types::get_ptr(&ty));
+            return Some(match size {
+                1 => layout_of::<u8>(),
+                2 => layout_of::<u16>(),
+                4 => layout_of::<u32>(),
+                8 => layout_of::<u64>(),
+                16 => layout_of::<u128>(),
+                _ => panic!("cannot compute the layout of {:?}", ty),
+            });
+        }
+    }
+    let long_double = Type::from_const(&ctx, gcc_jit_types_GCC_JIT_TYPE_LONG_DOUBLE);
+    if ty == ctx.new_type::<f32>() {
+        Some(layout_of::<f32>())
+    } else if ty == ctx.new_type::<f64>() {
+        Some(layout_of::<f64>())
+    } else if ty == long_double {
+        Some(long_double_layout())
+    } else if ty == ctx.new_type::<Complex<f32>>() {
+        Some(layout_of::<Complex<f32>>())
+    } else if ty == ctx.new_type::<Complex<f64>>() {
+        Some(layout_of::<Complex<f64>>())
+    } else if ty == Type::complex_long_double(&ctx) {
+        let part = long_double_layout();
+        Some(Layout {
+            size: 2 * part.size,
+            align: part.align,
+        })
+    } else {
+        None
+    }
+}
+
+/// C's long double, which has no Rust counterpart, so its layout is
+/// spelled out per target.
+fn long_double_layout() -> Layout {
+    if cfg!(target_arch = "x86") {
+        Layout { size: 12, align: 4 }
+    } else if cfg!(any(
+        target_arch = "arm",
+        all(target_arch = "aarch64", target_vendor = "apple")
+    )) {
+        layout_of::<f64>()
+    } else if cfg!(any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "loongarch64"
+    )) {
+        Layout {
+            size: 16,
+            align: 16,
+        }
+    } else {
+        panic!("the layout of long double is not known for this target")
+    }
+}
+
+/// Lays out a struct or union with the given fields, returning its layout
+/// and the offset of every field in bits.
+///
+/// Bit-fields follow GCC's rule for the System V ABIs: a bit-field goes
+/// right after the previous field unless that would make it straddle the
+/// boundary of a unit of its declared type, in which case it starts the
+/// next unit. Its declared type counts towards the alignment of the
+/// struct.
+fn record_layout(ty: Type, fields: &[Field]) -> (Layout, Vec<usize>) {
+    let is_union = cdecl::is_union(ty);
+    let mut align = 1;
+    let mut end = 0;
+    let mut offsets = Vec::with_capacity(fields.len());
+    for &field in fields {
+        let (field_ty, width) = match cdecl::field_type(field) {
+            Some(field_ty) => field_ty,
+            None => panic!(
+                "cannot compute the layout of {:?}: field {:?} wasn't made by this crate",
+                ty, field
+            ),
+        };
+        let layout = type_layout(field_ty);
+        align = align.max(layout.align);
+        let (unit, unit_align) = (8 * layout.size, 8 * layout.align);
+        let bits = width.map_or(unit, |width| width as usize);
+        let offset = if is_union {
+            0
+        } else if width.is_some() && end % unit_align + bits <= unit {
+            end
+        } else {
+            align_up(end, unit_align)
+        };
+        offsets.push(offset);
+        end = end.max(offset + bits);
+    }
+    let layout = Layout {
+        size: align_up(align_up(end, 8) / 8, align),
+        align,
+    };
+    (layout, offsets)
+}
+
+fn align_up(value: usize, align: usize) -> usize {
+    value.div_ceil(align) * align
+}
+
+/// Returns the byte offsets of the given fields of a struct or union type,
+/// which must be among its fields and can't be bit-fields.
+pub(crate) fn field_offsets(ty: Type, fields: &[Field]) -> Vec<usize> {
+    let members = match cdecl::members(ty) {
+        Some(members) => members,
+        None => panic!("{:?} is not a struct or union with known fields", ty),
+    };
+    let offsets = measure(ty).offsets;
+    fields
+        .iter()
+        .map(|&field| {
+            let index = match members.iter().position(|&member| key(member) == key(field)) {
+                Some(index) => index,
+                None => panic!("{:?} is not a field of {:?}", field, ty),
+            };
+            match offsets[index] {
+                Some(offset) => offset,
+                None => panic!("bit-field {:?} of {:?} has no byte offset", field, ty),
+            }
+        })
+        .collect()
+}
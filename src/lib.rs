@@ -2,14 +2,24 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 pub use gccjit_sys as sys;
+pub mod arith;
+pub mod atomics;
 pub mod block;
+pub mod builder;
+pub mod cdecl;
+pub mod constant;
 pub mod ctx;
+pub mod enums;
 pub mod field;
 pub mod function;
+pub mod layout;
 pub mod location;
 pub mod lvalue;
 pub mod object;
 pub mod parameter;
+mod promote;
 pub mod rvalue;
+pub mod slice;
 pub mod structs;
 pub mod ty;
+pub mod typed;
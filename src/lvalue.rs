@@ -1,4 +1,5 @@
-use crate::ctx::Context;
+use crate::cdecl;
+use crate::ctx::{Context, Visibility};
 use crate::field;
 use crate::field::Field;
 use crate::location;
@@ -8,8 +9,10 @@ use crate::object::{Object, ToObject};
 use crate::rvalue;
 use crate::rvalue::{RValue, ToRValue};
 use gccjit_sys;
+use std::ffi::CString;
 use std::fmt;
 use std::marker::PhantomData;
+use std::mem;
 use std::ptr;
 
 /// An LValue in gccjit represents a value that has a concrete
@@ -21,6 +24,20 @@ pub struct LValue {
     ptr: *mut gccjit_sys::gcc_jit_lvalue,
 }
 
+/// The thread-local storage model of a global, as in GCC's `tls_model`
+/// attribute. Any model other than None makes the global thread-local, so
+/// that every thread has its own copy. LocalExec only links into
+/// executables, not into the shared objects that Context::compile loads.
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+pub enum TlsModel {
+    None = 0,
+    GlobalDynamic = 1,
+    LocalDynamic = 2,
+    InitialExec = 3,
+    LocalExec = 4,
+}
+
 /// ToLValue is a trait implemented by types that can be converted (or treated
 /// as) LValues.
 pub trait ToLValue {
@@ -70,6 +87,13 @@ impl LValue {
         }
     }
 
+    /// Given an LValue x of struct or union type, gets an LValue for the
+    /// field access x.name. Panics if the type has no such field.
+    pub fn field(&self, loc: Option<Location>, name: &str) -> LValue {
+        let field = cdecl::field_named(self.to_rvalue().get_type(), name);
+        self.access_field(loc, field)
+    }
+
     /// Given an LValue x, returns the RValue address of x, akin to C's &x.
     pub fn get_address(&self, loc: Option<Location>) -> RValue {
         let loc_ptr = match loc {
@@ -81,6 +105,67 @@ impl LValue {
             rvalue::from_ptr(ptr)
         }
     }
+
+    /// Makes a global thread-local, with the given TLS model. None makes it
+    /// an ordinary global again.
+    pub fn set_tls_model(&self, model: TlsModel) {
+        unsafe {
+            gccjit_sys::gcc_jit_lvalue_set_tls_model(self.ptr, mem::transmute(model));
+        }
+    }
+
+    /// Places a global in the named section of the object file, like GCC's
+    /// `section` attribute.
+    pub fn set_link_section<S: AsRef<str>>(&self, name: S) {
+        unsafe {
+            let cstr = CString::new(name.as_ref()).unwrap();
+            gccjit_sys::gcc_jit_lvalue_set_link_section(self.ptr, cstr.as_ptr());
+        }
+    }
+
+    /// Sets the visibility of a global's symbol.
+    pub fn set_visibility(&self, visibility: Visibility) {
+        unsafe {
+            gccjit_sys::gcc_jit_lvalue_add_string_attribute(
+                self.ptr,
+                gccjit_sys::gcc_jit_variable_attribute_GCC_JIT_VARIABLE_ATTRIBUTE_VISIBILITY,
+                visibility.as_cstr().as_ptr(),
+            );
+        }
+    }
+
+    /// Pins a variable to a hardware register, like C's
+    /// `register long x asm("r12")`. The register name is target-specific.
+    pub fn set_register_name<S: AsRef<str>>(&self, name: S) {
+        unsafe {
+            let cstr = CString::new(name.as_ref()).unwrap();
+            gccjit_sys::gcc_jit_lvalue_set_register_name(self.ptr, cstr.as_ptr());
+        }
+    }
+
+    /// Sets the initial value of a global from raw bytes, which are copied.
+    /// gccjit only accepts this for globals whose type is an array of
+    /// integers, such as a lookup table, and the number of bytes must match
+    /// the size of the array.
+    pub fn set_initializer(&self, bytes: &[u8]) {
+        unsafe {
+            gccjit_sys::gcc_jit_global_set_initializer(
+                self.ptr,
+                bytes.as_ptr() as *const _,
+                bytes.len(),
+            );
+        }
+    }
+
+    /// Sets the initial value of a global to a constant expression, such as
+    /// a constant, a constructor made of constants or the address of
+    /// another global.
+    pub fn set_initializer_rvalue<T: ToRValue>(&self, value: T) {
+        let rvalue = value.to_rvalue();
+        unsafe {
+            gccjit_sys::gcc_jit_global_set_initializer_rvalue(self.ptr, rvalue::get_ptr(&rvalue));
+        }
+    }
 }
 
 pub unsafe fn from_ptr(ptr: *mut gccjit_sys::gcc_jit_lvalue) -> LValue {
@@ -1,4 +1,4 @@
-use crate::ctx::Context;
+use crate::ctx::{self, Context};
 use gccjit_sys;
 use std::ffi::CStr;
 use std::fmt;
@@ -24,6 +24,13 @@ impl fmt::Debug for Object {
     }
 }
 
+impl Object {
+    /// Returns the context that this object was created in.
+    pub fn get_context(&self) -> Context {
+        unsafe { ctx::context_from_ptr(gccjit_sys::gcc_jit_object_get_context(self.ptr)) }
+    }
+}
+
 /// ToObject is a trait implemented by types that can be upcast to Object.
 pub trait ToObject {
     fn to_object(&self) -> Object;
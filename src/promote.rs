@@ -0,0 +1,237 @@
+use crate::block::{BinaryOp, ComparisonOp};
+use crate::ctx::Context;
+use crate::layout;
+use crate::location::Location;
+use crate::rvalue::RValue;
+use crate::sys::*;
+use crate::ty::Type;
+
+/// Where a type sits in C's conversion rules. Integers are ranked as in
+/// C11 6.3.1.1, floating-point types by precision.
+#[derive(Copy, Clone)]
+enum Class {
+    Int { rank: u32, signed: bool },
+    Float { rank: u32, complex: bool },
+}
+
+/// The rank of int64_t, which is long on LP64 targets and long long on
+/// 64-bit Windows.
+const INT64_RANK: u32 = if cfg!(windows) { 5 } else { 4 };
+
+/// The rank of size_t, which has the rank of the type isize maps to: long,
+/// except on 64-bit Windows, where it is long long.
+#[cfg(not(all(windows, target_pointer_width = "64")))]
+const SIZE_T_RANK: u32 = 4;
+#[cfg(all(windows, target_pointer_width = "64"))]
+const SIZE_T_RANK: u32 = 5;
+
+const INTS: &[(u32, u32, bool)] = &[
+    (gcc_jit_types_GCC_JIT_TYPE_BOOL, 0, false),
+    (gcc_jit_types_GCC_JIT_TYPE_CHAR, 1, true),
+    (gcc_jit_types_GCC_JIT_TYPE_SIGNED_CHAR, 1, true),
+    (gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_CHAR, 1, false),
+    (gcc_jit_types_GCC_JIT_TYPE_SHORT, 2, true),
+    (gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_SHORT, 2, false),
+    (gcc_jit_types_GCC_JIT_TYPE_INT, 3, true),
+    (gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_INT, 3, false),
+    (gcc_jit_types_GCC_JIT_TYPE_LONG, 4, true),
+    (gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_LONG, 4, false),
+    (gcc_jit_types_GCC_JIT_TYPE_SIZE_T, SIZE_T_RANK, false),
+    (gcc_jit_types_GCC_JIT_TYPE_LONG_LONG, 5, true),
+    (gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_LONG_LONG, 5, false),
+    (gcc_jit_types_GCC_JIT_TYPE_INT128_T, 6, true),
+    (gcc_jit_types_GCC_JIT_TYPE_UINT128_T, 6, false),
+    (gcc_jit_types_GCC_JIT_TYPE_INT8_T, 1, true),
+    (gcc_jit_types_GCC_JIT_TYPE_UINT8_T, 1, false),
+    (gcc_jit_types_GCC_JIT_TYPE_INT16_T, 2, true),
+    (gcc_jit_types_GCC_JIT_TYPE_UINT16_T, 2, false),
+    (gcc_jit_types_GCC_JIT_TYPE_INT32_T, 3, true),
+    (gcc_jit_types_GCC_JIT_TYPE_UINT32_T, 3, false),
+    (gcc_jit_types_GCC_JIT_TYPE_INT64_T, INT64_RANK, true),
+    (gcc_jit_types_GCC_JIT_TYPE_UINT64_T, INT64_RANK, false),
+];
+
+const FLOATS: &[(u32, u32, bool)] = &[
+    (gcc_jit_types_GCC_JIT_TYPE_FLOAT, 1, false),
+    (gcc_jit_types_GCC_JIT_TYPE_DOUBLE, 2, false),
+    (gcc_jit_types_GCC_JIT_TYPE_LONG_DOUBLE, 3, false),
+    (gcc_jit_types_GCC_JIT_TYPE_COMPLEX_FLOAT, 1, true),
+    (gcc_jit_types_GCC_JIT_TYPE_COMPLEX_DOUBLE, 2, true),
+    (gcc_jit_types_GCC_JIT_TYPE_COMPLEX_LONG_DOUBLE, 3, true),
+];
+
+/// Classifies a type, ignoring its qualifiers.
+fn classify(ctx: &Context, ty: Type) -> Option<Class> {
+    let ty = ty.unqualified();
+    for &(kind, rank, signed) in INTS {
+        if ty == Type::from_const(ctx, kind) {
+            return Some(Class::Int { rank, signed });
+        }
+    }
+    for &(kind, rank, complex) in FLOATS {
+        if ty == Type::from_const(ctx, kind) {
+            return Some(Class::Float { rank, complex });
+        }
+    }
+    None
+}
+
+fn float_type(ctx: &Context, rank: u32, complex: bool) -> Type {
+    let &(kind, _, _) = FLOATS
+        .iter()
+        .find(|&&(_, r, c)| r == rank && c == complex)
+        .unwrap();
+    Type::from_const(ctx, kind)
+}
+
+/// The unsigned type with the same rank as a signed one.
+fn unsigned_of(ctx: &Context, ty: Type) -> Type {
+    let kind = if ty == Type::from_const(ctx, gcc_jit_types_GCC_JIT_TYPE_INT64_T) {
+        gcc_jit_types_GCC_JIT_TYPE_UINT64_T
+    } else if ty == Type::from_const(ctx, gcc_jit_types_GCC_JIT_TYPE_INT32_T) {
+        gcc_jit_types_GCC_JIT_TYPE_UINT32_T
+    } else if ty == Type::from_const(ctx, gcc_jit_types_GCC_JIT_TYPE_INT128_T) {
+        gcc_jit_types_GCC_JIT_TYPE_UINT128_T
+    } else if ty == Type::from_const(ctx, gcc_jit_types_GCC_JIT_TYPE_LONG_LONG) {
+        gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_LONG_LONG
+    } else if ty == Type::from_const(ctx, gcc_jit_types_GCC_JIT_TYPE_LONG) {
+        gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_LONG
+    } else {
+        gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_INT
+    };
+    Type::from_const(ctx, kind)
+}
+
+/// The integer promotions: everything ranked below int becomes int.
+fn promote_int(ctx: &Context, ty: Type) -> Type {
+    match classify(ctx, ty) {
+        Some(Class::Int { rank, .. }) if rank < 3 => ctx.new_type::<i32>(),
+        _ => ty,
+    }
+}
+
+/// Returns the type C's usual arithmetic conversions give for operands of
+/// types `lhs` and `rhs`, or None if either isn't an arithmetic type.
+pub(crate) fn common_type(ctx: &Context, lhs: Type, rhs: Type) -> Option<Type> {
+    let lhs = lhs.unqualified();
+    let rhs = rhs.unqualified();
+    let lhs_class = classify(ctx, lhs)?;
+    let rhs_class = classify(ctx, rhs)?;
+    let float_rank = |class| match class {
+        Class::Float { rank, .. } => rank,
+        Class::Int { .. } => 0,
+    };
+    let is_complex = |class| match class {
+        Class::Float { complex, .. } => complex,
+        Class::Int { .. } => false,
+    };
+    let rank = float_rank(lhs_class).max(float_rank(rhs_class));
+    if rank > 0 {
+        let complex = is_complex(lhs_class) || is_complex(rhs_class);
+        return Some(float_type(ctx, rank, complex));
+    }
+
+    let lhs = promote_int(ctx, lhs);
+    let rhs = promote_int(ctx, rhs);
+    if lhs == rhs {
+        return Some(lhs);
+    }
+    let (lhs_rank, lhs_signed) = int_class(ctx, lhs);
+    let (rhs_rank, rhs_signed) = int_class(ctx, rhs);
+    if lhs_signed == rhs_signed {
+        return Some(if rhs_rank > lhs_rank { rhs } else { lhs });
+    }
+    let (signed, signed_rank, unsigned, unsigned_rank) = if lhs_signed {
+        (lhs, lhs_rank, rhs, rhs_rank)
+    } else {
+        (rhs, rhs_rank, lhs, lhs_rank)
+    };
+    if unsigned_rank >= signed_rank {
+        Some(unsigned)
+    } else if layout::size_of(signed) > layout::size_of(unsigned) {
+        Some(signed)
+    } else {
+        Some(unsigned_of(ctx, signed))
+    }
+}
+
+fn int_class(ctx: &Context, ty: Type) -> (u32, bool) {
+    match classify(ctx, ty) {
+        Some(Class::Int { rank, signed }) => (rank, signed),
+        _ => unreachable!(),
+    }
+}
+
+fn convert(ctx: &Context, loc: Option<Location>, value: RValue, ty: Type) -> RValue {
+    if value.get_type() == ty {
+        value
+    } else {
+        ctx.new_cast(loc, value, ty)
+    }
+}
+
+/// Converts both operands to their common type. Operands of the same
+/// non-arithmetic type, such as two pointers, are left alone.
+pub(crate) fn convert_operands(
+    ctx: &Context,
+    loc: Option<Location>,
+    lhs: RValue,
+    rhs: RValue,
+) -> (RValue, RValue, Type) {
+    let lhs_ty = lhs.get_type();
+    let rhs_ty = rhs.get_type();
+    match common_type(ctx, lhs_ty, rhs_ty) {
+        Some(ty) => (convert(ctx, loc, lhs, ty), convert(ctx, loc, rhs, ty), ty),
+        None if lhs_ty == rhs_ty => (lhs, rhs, lhs_ty),
+        None => panic!(
+            "no usual arithmetic conversion between {:?} and {:?}",
+            lhs_ty, rhs_ty
+        ),
+    }
+}
+
+/// Builds `lhs op rhs` with C's conversions. Shifts take the promoted type
+/// of the left operand, and the right operand is converted to that type
+/// too, since gccjit wants both operands of one type; C would promote it on
+/// its own, which gives the same result for every shift count in range.
+/// `&&` and `||` convert both operands to bool and give a bool.
+pub(crate) fn binary_op(
+    ctx: &Context,
+    loc: Option<Location>,
+    op: BinaryOp,
+    lhs: RValue,
+    rhs: RValue,
+) -> RValue {
+    match op {
+        BinaryOp::LShift | BinaryOp::RShift => {
+            let ty = promote_int(ctx, lhs.get_type().unqualified());
+            let lhs = convert(ctx, loc, lhs, ty);
+            let rhs = convert(ctx, loc, rhs, ty);
+            ctx.new_binary_op(loc, op, ty, lhs, rhs)
+        }
+        BinaryOp::LogicalAnd | BinaryOp::LogicalOr => {
+            let lhs = truth_value(ctx, loc, lhs);
+            let rhs = truth_value(ctx, loc, rhs);
+            ctx.new_binary_op(loc, op, ctx.new_type::<bool>(), lhs, rhs)
+        }
+        _ => {
+            let (lhs, rhs, ty) = convert_operands(ctx, loc, lhs, rhs);
+            ctx.new_binary_op(loc, op, ty, lhs, rhs)
+        }
+    }
+}
+
+/// Converts a scalar to bool the way C's `if` does: bools are returned as
+/// they are, numbers are compared against zero and pointers against NULL.
+pub(crate) fn truth_value(ctx: &Context, loc: Option<Location>, value: RValue) -> RValue {
+    let ty = value.get_type();
+    if ty.unqualified() == ctx.new_type::<bool>() {
+        return value;
+    }
+    let zero = if classify(ctx, ty).is_some() {
+        ctx.new_rvalue_zero(ty)
+    } else {
+        ctx.new_null(ty)
+    };
+    ctx.new_comparison(loc, ComparisonOp::NotEquals, value, zero)
+}
@@ -2,16 +2,23 @@ use crate::ctx::Context;
 use crate::object;
 use crate::rvalue;
 use crate::ty as types;
-use crate::ty::Type;
+use crate::ty::{Complex, Type};
 use gccjit_sys;
 use object::{Object, ToObject};
 use std::fmt;
 use std::marker::PhantomData;
 use std::mem;
-use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Rem, Shl, Shr, Sub};
+use std::ops::{
+    Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Div,
+    DivAssign, Mul, MulAssign, Neg, Not, Rem, RemAssign, Shl, ShlAssign, Shr, ShrAssign, Sub,
+    SubAssign,
+};
 use std::ptr;
 
-use crate::block::BinaryOp;
+use crate::block::{BinaryOp, ComparisonOp, UnaryOp};
+use crate::cdecl;
+use crate::constant::IntoConst;
+use crate::enums;
 use crate::field;
 use crate::field::Field;
 use crate::location;
@@ -39,8 +46,12 @@ impl ToObject for RValue {
     }
 }
 
+/// Enum constants made by EnumType::variant show the variant's name.
 impl fmt::Debug for RValue {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        if let Some(name) = enums::variant_name(*self) {
+            return fmt.write_str(&name);
+        }
         let obj = self.to_object();
         obj.fmt(fmt)
     }
@@ -52,43 +63,229 @@ impl ToRValue for RValue {
     }
 }
 
-macro_rules! binary_operator_for {
-    ($ty:ty, $name:ident, $op:expr) => {
-        impl $ty for RValue {
-            type Output = RValue;
+/// Operand is implemented by everything the overloaded operators accept:
+/// anything that converts to an RValue, and Rust literals. A literal becomes
+/// a constant of the type of the other operand.
+pub trait Operand {
+    fn to_operand(&self, other: Type) -> RValue;
+}
 
-            fn $name(self, rhs: RValue) -> RValue {
-                unsafe {
-                    let rhs_rvalue = rhs.to_rvalue();
-                    let obj_ptr = object::get_ptr(&self.to_object());
-                    let ctx_ptr = gccjit_sys::gcc_jit_object_get_context(obj_ptr);
-                    let ty = rhs.get_type();
-                    let ptr = gccjit_sys::gcc_jit_context_new_binary_op(
-                        ctx_ptr,
-                        ptr::null_mut(),
-                        mem::transmute($op),
-                        types::get_ptr(&ty),
-                        self.ptr,
-                        rhs_rvalue.ptr,
+impl<T: ToRValue> Operand for T {
+    fn to_operand(&self, _other: Type) -> RValue {
+        self.to_rvalue()
+    }
+}
+
+macro_rules! const_operand_for {
+    ($($ty:ty),*) => {
+        $(impl Operand for $ty {
+            fn to_operand(&self, other: Type) -> RValue {
+                self.into_const(&other.to_object().get_context(), other)
+            }
+        })*
+    };
+}
+
+const_operand_for!(i8, u8, i16, u16, i32, u32, i64, u64, isize, usize, i128, u128);
+
+/// Float literals can't become integer constants: `x + 2.5` on an int
+/// would silently drop the fraction, so it panics instead.
+macro_rules! float_operand_for {
+    ($($ty:ty),*) => {
+        $(impl Operand for $ty {
+            fn to_operand(&self, other: Type) -> RValue {
+                if other.unqualified().is_integral() {
+                    panic!(
+                        "the literal {:?} can't be an operand of integer type {:?}, cast one side first",
+                        self, other
                     );
-                    from_ptr(ptr)
                 }
+                self.into_const(&other.to_object().get_context(), other)
+            }
+        })*
+    };
+}
+
+float_operand_for!(f32, f64);
+
+/// Builds `lhs op rhs`. Apart from mixing a complex and a real operand, both
+/// operands must have the same type, which is also the result type; use
+/// Context::new_promoted_binary_op for C's usual arithmetic conversions.
+fn binary_op(op: BinaryOp, lhs: RValue, rhs: RValue) -> RValue {
+    unsafe {
+        let (lhs, rhs) = promote_complex(lhs, rhs);
+        let ty = same_type(lhs.get_type(), rhs.get_type());
+        let obj_ptr = object::get_ptr(&lhs.to_object());
+        let ctx_ptr = gccjit_sys::gcc_jit_object_get_context(obj_ptr);
+        let ptr = gccjit_sys::gcc_jit_context_new_binary_op(
+            ctx_ptr,
+            ptr::null_mut(),
+            mem::transmute(op),
+            types::get_ptr(&ty),
+            lhs.ptr,
+            rhs.ptr,
+        );
+        from_ptr(ptr)
+    }
+}
+
+/// Returns the unqualified type of two operands, or panics if they differ.
+fn same_type(lhs: Type, rhs: Type) -> Type {
+    let (lhs, rhs) = (lhs.unqualified(), rhs.unqualified());
+    if lhs != rhs {
+        panic!(
+            "operands have different types {:?} and {:?}, cast one side or use Context::new_promoted_binary_op",
+            lhs, rhs
+        );
+    }
+    lhs
+}
+
+/// Adds `lhs op= rhs` to the current block of the context. The operator
+/// traits have no room for a block argument, so this is the block set by
+/// Context::set_current_block; use Block::add_assignment_op to name the
+/// block explicitly.
+fn assignment_op(op: BinaryOp, lhs: LValue, rhs: RValue) {
+    let ctx = lhs.to_object().get_context();
+    same_type(lhs.to_rvalue().get_type(), rhs.get_type());
+    match ctx.current_block() {
+        Some(block) => block.add_assignment_op(None, lhs, op, rhs),
+        None => panic!(
+            "compound assignment to {:?} needs a current block, see Context::set_current_block",
+            lhs
+        ),
+    }
+}
+
+macro_rules! binary_operator_for {
+    ($ty:ident, $name:ident, $assign_ty:ident, $assign_name:ident, $op:expr) => {
+        impl<R: Operand> $ty<R> for RValue {
+            type Output = RValue;
+
+            fn $name(self, rhs: R) -> RValue {
+                let rhs = rhs.to_operand(self.get_type());
+                binary_op($op, self, rhs)
+            }
+        }
+
+        impl<R: Operand> $ty<R> for LValue {
+            type Output = RValue;
+
+            fn $name(self, rhs: R) -> RValue {
+                self.to_rvalue().$name(rhs)
             }
         }
+
+        /// Adds the assignment to the context's current block, see
+        /// Context::set_current_block. Panics if there is none.
+        impl<R: Operand> $assign_ty<R> for LValue {
+            fn $assign_name(&mut self, rhs: R) {
+                let rhs = rhs.to_operand(self.to_rvalue().get_type());
+                assignment_op($op, *self, rhs)
+            }
+        }
+
+        literal_operator_for!(
+            $ty, $name, $op, i8, u8, i16, u16, i32, u32, i64, u64, isize, usize, i128, u128, f32, f64
+        );
     };
 }
 
+macro_rules! literal_operator_for {
+    ($ty:ident, $name:ident, $op:expr, $($lit:ty),*) => {
+        $(
+            impl $ty<RValue> for $lit {
+                type Output = RValue;
+
+                fn $name(self, rhs: RValue) -> RValue {
+                    binary_op($op, self.to_operand(rhs.get_type()), rhs)
+                }
+            }
+
+            impl $ty<LValue> for $lit {
+                type Output = RValue;
+
+                fn $name(self, rhs: LValue) -> RValue {
+                    self.$name(rhs.to_rvalue())
+                }
+            }
+        )*
+    };
+}
+
+/// Converts the real operand to the complex type when a complex and a real
+/// operand are mixed, like C's usual arithmetic conversions do.
+fn promote_complex(lhs: RValue, rhs: RValue) -> (RValue, RValue) {
+    let lhs_ty = lhs.get_type();
+    let rhs_ty = rhs.get_type();
+    if lhs_ty == rhs_ty {
+        return (lhs, rhs);
+    }
+    let ctx = lhs.to_object().get_context();
+    if lhs_ty.is_complex() && !rhs_ty.is_complex() {
+        (lhs, ctx.new_cast(None, rhs, lhs_ty))
+    } else if rhs_ty.is_complex() && !lhs_ty.is_complex() {
+        (ctx.new_cast(None, lhs, rhs_ty), rhs)
+    } else {
+        (lhs, rhs)
+    }
+}
+
 // Operator overloads for ease of manipulation of rvalues
-binary_operator_for!(Add, add, BinaryOp::Plus);
-binary_operator_for!(Sub, sub, BinaryOp::Minus);
-binary_operator_for!(Mul, mul, BinaryOp::Mult);
-binary_operator_for!(Div, div, BinaryOp::Divide);
-binary_operator_for!(Rem, rem, BinaryOp::Modulo);
-binary_operator_for!(BitAnd, bitand, BinaryOp::BitwiseAnd);
-binary_operator_for!(BitOr, bitor, BinaryOp::BitwiseOr);
-binary_operator_for!(BitXor, bitxor, BinaryOp::BitwiseXor);
-binary_operator_for!(Shl<RValue>, shl, BinaryOp::LShift);
-binary_operator_for!(Shr<RValue>, shr, BinaryOp::RShift);
+binary_operator_for!(Add, add, AddAssign, add_assign, BinaryOp::Plus);
+binary_operator_for!(Sub, sub, SubAssign, sub_assign, BinaryOp::Minus);
+binary_operator_for!(Mul, mul, MulAssign, mul_assign, BinaryOp::Mult);
+binary_operator_for!(Div, div, DivAssign, div_assign, BinaryOp::Divide);
+binary_operator_for!(Rem, rem, RemAssign, rem_assign, BinaryOp::Modulo);
+binary_operator_for!(BitAnd, bitand, BitAndAssign, bitand_assign, BinaryOp::BitwiseAnd);
+binary_operator_for!(BitOr, bitor, BitOrAssign, bitor_assign, BinaryOp::BitwiseOr);
+binary_operator_for!(BitXor, bitxor, BitXorAssign, bitxor_assign, BinaryOp::BitwiseXor);
+binary_operator_for!(Shl, shl, ShlAssign, shl_assign, BinaryOp::LShift);
+binary_operator_for!(Shr, shr, ShrAssign, shr_assign, BinaryOp::RShift);
+
+fn unary_op(op: UnaryOp, value: RValue) -> RValue {
+    let ctx = value.to_object().get_context();
+    ctx.new_unary_op(None, op, value.get_type(), value)
+}
+
+/// `-x` is C's unary minus.
+impl Neg for RValue {
+    type Output = RValue;
+
+    fn neg(self) -> RValue {
+        unary_op(UnaryOp::Minus, self)
+    }
+}
+
+impl Neg for LValue {
+    type Output = RValue;
+
+    fn neg(self) -> RValue {
+        -self.to_rvalue()
+    }
+}
+
+/// `!x` is C's `!x` on bools and `~x` on everything else, as in Rust.
+impl Not for RValue {
+    type Output = RValue;
+
+    fn not(self) -> RValue {
+        let ctx = self.to_object().get_context();
+        if self.get_type() == ctx.new_type::<bool>() {
+            unary_op(UnaryOp::LogicalNegate, self)
+        } else {
+            unary_op(UnaryOp::BitwiseNegate, self)
+        }
+    }
+}
+
+impl Not for LValue {
+    type Output = RValue;
+
+    fn not(self) -> RValue {
+        !self.to_rvalue()
+    }
+}
 
 impl RValue {
     /// Gets the type of this RValue.
@@ -130,6 +327,114 @@ impl RValue {
         }
     }
 
+    /// Given an RValue x of struct or union type, returns x.name. Panics if
+    /// the type has no such field.
+    pub fn field(&self, loc: Option<Location>, name: &str) -> RValue {
+        let field = cdecl::field_named(self.get_type(), name);
+        self.access_field(loc, field)
+    }
+
+    /// Given a pointer p to a struct or union, returns the LValue p->name.
+    /// Panics if p isn't such a pointer or the type has no such field.
+    pub fn deref_field(&self, loc: Option<Location>, name: &str) -> LValue {
+        let pointee = self.pointee("deref_field");
+        self.dereference_field(loc, cdecl::field_named(pointee, name))
+    }
+
+    /// Returns the real part of a complex RValue, like C's creal().
+    pub fn real(&self, loc: Option<Location>) -> RValue {
+        self.complex_part(loc, "creal")
+    }
+
+    /// Returns the imaginary part of a complex RValue, like C's cimag().
+    pub fn imag(&self, loc: Option<Location>) -> RValue {
+        self.complex_part(loc, "cimag")
+    }
+
+    /// Calls the float, double or long double variant of a complex
+    /// builtin, e.g. __builtin_crealf for a _Complex float. Any other type
+    /// is converted to _Complex double first.
+    fn complex_part(&self, loc: Option<Location>, name: &str) -> RValue {
+        let ctx = self.to_object().get_context();
+        let ty = self.get_type();
+        let (suffix, arg_ty) = if ty == ctx.new_type::<Complex<f32>>() {
+            ("f", ty)
+        } else if ty == Type::complex_long_double(&ctx) {
+            ("l", ty)
+        } else {
+            ("", ctx.new_type::<Complex<f64>>())
+        };
+        let arg = if ty == arg_ty {
+            *self
+        } else {
+            ctx.new_cast(loc, *self, arg_ty)
+        };
+        let builtin = ctx.get_builtin_function(format!("__builtin_{}{}", name, suffix));
+        ctx.new_call(loc, builtin, &[arg])
+    }
+
+    /// Returns `self == rhs`.
+    pub fn eq<R: Operand>(self, rhs: R) -> RValue {
+        self.compare(ComparisonOp::Equals, rhs)
+    }
+
+    /// Returns `self != rhs`.
+    pub fn ne<R: Operand>(self, rhs: R) -> RValue {
+        self.compare(ComparisonOp::NotEquals, rhs)
+    }
+
+    /// Returns `self < rhs`.
+    pub fn lt<R: Operand>(self, rhs: R) -> RValue {
+        self.compare(ComparisonOp::LessThan, rhs)
+    }
+
+    /// Returns `self <= rhs`.
+    pub fn le<R: Operand>(self, rhs: R) -> RValue {
+        self.compare(ComparisonOp::LessThanEquals, rhs)
+    }
+
+    /// Returns `self > rhs`.
+    pub fn gt<R: Operand>(self, rhs: R) -> RValue {
+        self.compare(ComparisonOp::GreaterThan, rhs)
+    }
+
+    /// Returns `self >= rhs`.
+    pub fn ge<R: Operand>(self, rhs: R) -> RValue {
+        self.compare(ComparisonOp::GreaterThanEquals, rhs)
+    }
+
+    /// Returns `self && rhs`. Operands that aren't bools are converted to
+    /// bool first.
+    pub fn and<R: Operand>(self, rhs: R) -> RValue {
+        self.logical(BinaryOp::LogicalAnd, rhs)
+    }
+
+    /// Returns `self || rhs`. Operands that aren't bools are converted to
+    /// bool first.
+    pub fn or<R: Operand>(self, rhs: R) -> RValue {
+        self.logical(BinaryOp::LogicalOr, rhs)
+    }
+
+    fn compare<R: Operand>(self, op: ComparisonOp, rhs: R) -> RValue {
+        let ctx = self.to_object().get_context();
+        let rhs = rhs.to_operand(self.get_type());
+        ctx.new_comparison(None, op, self, rhs)
+    }
+
+    fn logical<R: Operand>(self, op: BinaryOp, rhs: R) -> RValue {
+        let ctx = self.to_object().get_context();
+        let bool_ty = ctx.new_type::<bool>();
+        let as_bool = |value: RValue| {
+            if value.get_type() == bool_ty {
+                value
+            } else {
+                ctx.new_cast(None, value, bool_ty)
+            }
+        };
+        let rhs = as_bool(rhs.to_operand(bool_ty));
+        ctx.new_binary_op(None, op, bool_ty, as_bool(self), rhs)
+    }
+
     /// Given a RValue x, returns an RValue that represents *x.
     pub fn dereference(&self, loc: Option<Location>) -> LValue {
         let loc_ptr = match loc {
@@ -142,6 +447,135 @@ impl RValue {
             lvalue::from_ptr(ptr)
         }
     }
+
+    /// Given a pointer p and an integer i, returns `p + i`, i.e. the
+    /// address of `p[i]`. Like Rust's `offset`, i counts elements, not bytes.
+    pub fn ptr_offset<I: ToRValue>(&self, loc: Option<Location>, index: I) -> RValue {
+        let ctx = self.to_object().get_context();
+        self.sized_pointee("ptr_offset");
+        ctx.new_array_access(loc, *self, index).get_address(loc)
+    }
+
+    /// Given a pointer p and an integer n, returns p moved by n bytes,
+    /// with the type of p.
+    pub fn ptr_byte_add<I: ToRValue>(&self, loc: Option<Location>, bytes: I) -> RValue {
+        let ctx = self.to_object().get_context();
+        let ty = self.get_type();
+        self.pointee("ptr_byte_add");
+        let byte_ptr = ctx.new_cast(loc, *self, ctx.new_type::<u8>().make_pointer());
+        let moved = ctx.new_array_access(loc, byte_ptr, bytes).get_address(loc);
+        ctx.new_cast(loc, moved, ty)
+    }
+
+    /// Given pointers p and q to the same type, returns the number of
+    /// elements from q to p as an isize, like Rust's `p.offset_from(q)`.
+    /// The distance must be a multiple of the element size.
+    pub fn ptr_diff<R: ToRValue>(&self, loc: Option<Location>, origin: R) -> RValue {
+        let ctx = self.to_object().get_context();
+        let origin = origin.to_rvalue();
+        let pointee = self.sized_pointee("ptr_diff");
+        if origin.get_type() != self.get_type() {
+            panic!(
+                "ptr_diff needs pointers of the same type, not {:?} and {:?}",
+                self.get_type(),
+                origin.get_type()
+            );
+        }
+        let usize_ty = ctx.new_type::<usize>();
+        let isize_ty = ctx.new_type::<isize>();
+        let bytes = ctx.new_binary_op(
+            loc,
+            BinaryOp::Minus,
+            usize_ty,
+            self.ptr_to_usize(loc),
+            origin.ptr_to_usize(loc),
+        );
+        let bytes = ctx.new_cast(loc, bytes, isize_ty);
+        let size = ctx.new_cast(loc, ctx.new_sizeof(pointee), isize_ty);
+        ctx.new_binary_op(loc, BinaryOp::Divide, isize_ty, bytes, size)
+    }
+
+    /// Returns the address a pointer holds as a usize, like `p as usize`.
+    pub fn ptr_to_usize(&self, loc: Option<Location>) -> RValue {
+        let ctx = self.to_object().get_context();
+        ctx.new_bitcast(loc, *self, ctx.new_type::<usize>())
+    }
+
+    /// Returns a pointer of type `ty` holding an address given as an
+    /// integer, like `n as *mut T`. Integers other than usize are converted
+    /// to usize first.
+    pub fn usize_to_ptr(&self, loc: Option<Location>, ty: Type) -> RValue {
+        let ctx = self.to_object().get_context();
+        let usize_ty = ctx.new_type::<usize>();
+        let value = if self.get_type() == usize_ty {
+            *self
+        } else {
+            ctx.new_cast(loc, *self, usize_ty)
+        };
+        ctx.new_bitcast(loc, value, ty)
+    }
+
+    /// Rounds an integer or the address in a pointer up to a multiple of
+    /// `align`, which must be a power of two. Pointers keep their type.
+    pub fn align_up(&self, loc: Option<Location>, align: usize) -> RValue {
+        self.align(loc, align, true)
+    }
+
+    /// Rounds an integer or the address in a pointer down to a multiple of
+    /// `align`, which must be a power of two. Pointers keep their type.
+    pub fn align_down(&self, loc: Option<Location>, align: usize) -> RValue {
+        self.align(loc, align, false)
+    }
+
+    fn align(&self, loc: Option<Location>, align: usize, up: bool) -> RValue {
+        if !align.is_power_of_two() {
+            panic!("alignment {} is not a power of two", align);
+        }
+        let ctx = self.to_object().get_context();
+        let ty = self.get_type();
+        let (value, int_ty) = if ty.is_integral() {
+            (*self, ty)
+        } else {
+            self.pointee("align");
+            (self.ptr_to_usize(loc), ctx.new_type::<usize>())
+        };
+        let mask = ctx.new_rvalue_from_u64(int_ty, align as u64 - 1);
+        let value = if up {
+            ctx.new_binary_op(loc, BinaryOp::Plus, int_ty, value, mask)
+        } else {
+            value
+        };
+        let mask = ctx.new_unary_op(loc, UnaryOp::BitwiseNegate, int_ty, mask);
+        let aligned = ctx.new_binary_op(loc, BinaryOp::BitwiseAnd, int_ty, value, mask);
+        if ty.is_integral() {
+            aligned
+        } else {
+            aligned.usize_to_ptr(loc, ty)
+        }
+    }
+
+    /// Like pointee, but also panics for `void *`, whose elements have no
+    /// size.
+    fn sized_pointee(&self, operation: &str) -> Type {
+        let pointee = self.pointee(operation);
+        if pointee.unqualified() == self.to_object().get_context().new_type::<()>() {
+            panic!("{} needs a pointer to a sized type, not {:?}", operation, self.get_type());
+        }
+        pointee
+    }
+
+    /// The type this pointer points to. Panics, naming the operation, if
+    /// this isn't a data pointer.
+    fn pointee(&self, operation: &str) -> Type {
+        match self.get_type().get_pointee() {
+            Some(pointee) => pointee,
+            None => panic!(
+                "{} needs a data pointer, not {:?}",
+                operation,
+                self.get_type()
+            ),
+        }
+    }
 }
 
 pub unsafe fn from_ptr(ptr: *mut gccjit_sys::gcc_jit_rvalue) -> RValue {
@@ -0,0 +1,195 @@
+//! Slices as a `{ptr, len}` struct, so that Rust's `&[T]`, `&mut [T]` and
+//! `&str` can be passed to and from jitted code as one value.
+//!
+//! The Typeable impls rely on rustc laying out a slice reference as a data
+//! pointer followed by a usize length, which is what it does on every
+//! target, but which is not a language guarantee.
+use std::collections::HashMap;
+
+use crate::block::{self, BinaryOp, Block, ComparisonOp};
+use crate::cdecl;
+use crate::ctx::{self, Context};
+use crate::field::Field;
+use crate::location::Location;
+use crate::lvalue::LValue;
+use crate::object::ToObject;
+use crate::rvalue::{RValue, ToRValue};
+use crate::structs::Struct;
+use crate::ty::{Type, Typeable};
+
+/// The struct type `struct { T *ptr; size_t len; }` for some element type
+/// T. There is one per element type and context, so slices of the same
+/// element type have the same Type. Element types are compared by identity;
+/// the Typeable impls make each const, array and function pointer type
+/// once per context, so `&[&T]` and the like are found again.
+///
+/// The value methods may evaluate the slice and index operands more than
+/// once, so operands with side effects should be stored in a local first.
+#[derive(Copy, Clone, Debug)]
+pub struct SliceType {
+    strukt: Struct,
+    ptr: Field,
+    len: Field,
+    element: Type,
+}
+
+/// The slice types of a context, by element type.
+#[derive(Default)]
+struct SliceTypes(HashMap<Type, SliceType>);
+
+impl SliceType {
+    /// Gets the slice type for the given element type, creating it on first
+    /// use.
+    pub fn new(ctx: &Context, element: Type) -> SliceType {
+        let slices = ctx::context_state::<SliceTypes>(ctx);
+        if let Some(&slice) = slices.borrow().0.get(&element) {
+            return slice;
+        }
+        let ptr = ctx.new_field(None, element.make_pointer(), "ptr");
+        let len = ctx.new_field(None, ctx.new_type::<usize>(), "len");
+        let name: String = format!("slice_{}", cdecl::declare(element, ""))
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let slice = SliceType {
+            strukt: ctx.new_struct_type(None, name, &[ptr, len]),
+            ptr,
+            len,
+            element,
+        };
+        slices.borrow_mut().0.insert(element, slice);
+        slice
+    }
+
+    pub fn as_type(&self) -> Type {
+        self.strukt.as_type()
+    }
+
+    pub fn element_type(&self) -> Type {
+        self.element
+    }
+
+    pub fn ptr_field(&self) -> Field {
+        self.ptr
+    }
+
+    pub fn len_field(&self) -> Field {
+        self.len
+    }
+
+    /// Builds a slice from a pointer to its first element and a length.
+    pub fn new_slice<P: ToRValue, L: ToRValue>(
+        &self,
+        loc: Option<Location>,
+        ptr: P,
+        len: L,
+    ) -> RValue {
+        let ctx = self.context();
+        let len = to_usize(&ctx, loc, len.to_rvalue());
+        ctx.new_struct_constructor(
+            loc,
+            self.as_type(),
+            &[(self.ptr, ptr.to_rvalue()), (self.len, len)],
+        )
+    }
+
+    /// Gets the pointer to the first element of a slice.
+    pub fn ptr<S: ToRValue>(&self, loc: Option<Location>, slice: S) -> RValue {
+        slice.to_rvalue().access_field(loc, self.ptr)
+    }
+
+    /// Gets the number of elements of a slice, as a usize.
+    pub fn len<S: ToRValue>(&self, loc: Option<Location>, slice: S) -> RValue {
+        slice.to_rvalue().access_field(loc, self.len)
+    }
+
+    /// Gets the element at `index`, which may be any integer. The block ends
+    /// with a bounds check that traps when the index is out of bounds, like
+    /// Rust's panic, and the element is returned with the block to continue
+    /// in.
+    pub fn index<S: ToRValue, I: ToRValue>(
+        &self,
+        block: Block,
+        loc: Option<Location>,
+        slice: S,
+        index: I,
+    ) -> (LValue, Block) {
+        let ctx = self.context();
+        let slice = slice.to_rvalue();
+        let index = to_usize(&ctx, loc, index.to_rvalue());
+        let fails = ctx.new_comparison(
+            loc,
+            ComparisonOp::GreaterThanEquals,
+            index,
+            self.len(loc, slice),
+        );
+        let ok = block::trap_if(block, loc, fails, "bounds_check");
+        (ctx.new_array_access(loc, self.ptr(loc, slice), index), ok)
+    }
+
+    /// Gets the slice `slice[start..end]`. The block ends with a check that
+    /// traps unless `start <= end <= len`, and the new slice is returned
+    /// with the block to continue in.
+    pub fn subslice<S: ToRValue, B: ToRValue, E: ToRValue>(
+        &self,
+        block: Block,
+        loc: Option<Location>,
+        slice: S,
+        start: B,
+        end: E,
+    ) -> (RValue, Block) {
+        let ctx = self.context();
+        let slice = slice.to_rvalue();
+        let usize_ty = ctx.new_type::<usize>();
+        let start = to_usize(&ctx, loc, start.to_rvalue());
+        let end = to_usize(&ctx, loc, end.to_rvalue());
+        let reversed = ctx.new_comparison(loc, ComparisonOp::GreaterThan, start, end);
+        let too_long =
+            ctx.new_comparison(loc, ComparisonOp::GreaterThan, end, self.len(loc, slice));
+        let fails = ctx.new_binary_op(
+            loc,
+            BinaryOp::LogicalOr,
+            ctx.new_type::<bool>(),
+            reversed,
+            too_long,
+        );
+        let ok = block::trap_if(block, loc, fails, "bounds_check");
+        let ptr = self.ptr(loc, slice).ptr_offset(loc, start);
+        let len = ctx.new_binary_op(loc, BinaryOp::Minus, usize_ty, end, start);
+        (self.new_slice(loc, ptr, len), ok)
+    }
+
+    fn context(&self) -> Context {
+        self.as_type().to_object().get_context()
+    }
+}
+
+fn to_usize(ctx: &Context, loc: Option<Location>, value: RValue) -> RValue {
+    let usize_ty = ctx.new_type::<usize>();
+    if value.get_type() == usize_ty {
+        value
+    } else {
+        ctx.new_cast(loc, value, usize_ty)
+    }
+}
+
+/// `&[T]` is the slice type of T, see the module docs.
+impl<T: Typeable> Typeable for &[T] {
+    fn get_type(ctx: &Context) -> Type {
+        SliceType::new(ctx, T::get_type(ctx)).as_type()
+    }
+}
+
+/// `&mut [T]` has the same type as `&[T]`.
+impl<T: Typeable> Typeable for &mut [T] {
+    fn get_type(ctx: &Context) -> Type {
+        SliceType::new(ctx, T::get_type(ctx)).as_type()
+    }
+}
+
+/// `&str` is a slice of its UTF-8 bytes, as unsigned chars.
+impl Typeable for &str {
+    fn get_type(ctx: &Context) -> Type {
+        SliceType::new(ctx, ctx.new_type::<u8>()).as_type()
+    }
+}
@@ -1,10 +1,13 @@
 use gccjit_sys;
 
+use std::cell::RefCell;
 use std::fmt;
 use std::marker::PhantomData;
 use std::ptr;
+use std::rc::Rc;
 
-use crate::ctx::Context;
+use crate::cdecl;
+use crate::ctx::{self, Context};
 use crate::field;
 use crate::field::Field;
 use crate::location;
@@ -15,6 +18,9 @@ use crate::ty::Type;
 
 /// A Struct is gccjit's representation of a composite type. Despite the name,
 /// Struct can represent either a struct, an union, or an opaque named type.
+/// A Struct made by new_opaque_struct_type starts out as an OpaqueStruct and
+/// only becomes a Struct once its fields are set, or once it is explicitly
+/// kept opaque.
 #[derive(Copy, Clone)]
 pub struct Struct {
     ptr: *mut gccjit_sys::gcc_jit_struct,
@@ -28,7 +34,78 @@ impl Struct {
         }
     }
 
-    pub fn set_fields(&self, location: Option<Location>, fields: &[Field]) {
+    /// Gets the fields of the struct, in declaration order. A struct kept
+    /// opaque has none.
+    pub fn fields(&self) -> Vec<Field> {
+        cdecl::members(self.as_type()).unwrap_or_default()
+    }
+
+    /// Gets a field of the struct by name. Panics if there is none.
+    pub fn field(&self, name: &str) -> Field {
+        cdecl::field_named(self.as_type(), name)
+    }
+}
+
+/// A Union is a union type made by new_union_type. It is a Type in gccjit;
+/// the handle only adds access to its fields.
+#[derive(Copy, Clone)]
+pub struct Union {
+    ty: Type,
+}
+
+impl Union {
+    pub fn as_type(&self) -> Type {
+        self.ty
+    }
+
+    /// Gets the fields of the union, in declaration order.
+    pub fn fields(&self) -> Vec<Field> {
+        cdecl::members(self.ty).unwrap_or_default()
+    }
+
+    /// Gets a field of the union by name. Panics if there is none.
+    pub fn field(&self, name: &str) -> Field {
+        cdecl::field_named(self.ty, name)
+    }
+}
+
+impl ToObject for Union {
+    fn to_object(&self) -> Object {
+        self.ty.to_object()
+    }
+}
+
+impl fmt::Debug for Union {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.ty.fmt(fmt)
+    }
+}
+
+pub(crate) fn new_union(ty: Type) -> Union {
+    Union { ty }
+}
+
+/// An OpaqueStruct is a struct that has been declared but whose fields are
+/// not known yet, like C's `struct foo;`. Pointers to it can be used to
+/// build the field types of recursive structs. Its fields can only be set
+/// once, so set_fields consumes it.
+///
+/// Every OpaqueStruct must be completed with set_fields or given up with
+/// keep_opaque before the context is compiled.
+pub struct OpaqueStruct {
+    ptr: *mut gccjit_sys::gcc_jit_struct,
+}
+
+impl OpaqueStruct {
+    pub fn as_type(&self) -> Type {
+        unsafe {
+            let ptr = gccjit_sys::gcc_jit_struct_as_type(self.ptr);
+            types::from_ptr(ptr)
+        }
+    }
+
+    /// Sets the fields of the struct, which completes it.
+    pub fn set_fields(self, location: Option<Location>, fields: &[Field]) -> Struct {
         let loc_ptr = match location {
             Some(loc) => unsafe { location::get_ptr(&loc) },
             None => ptr::null_mut(),
@@ -46,6 +123,73 @@ impl Struct {
                 fields_ptrs.as_mut_ptr(),
             );
         }
+        cdecl::record_members(self.as_type(), fields);
+        self.keep_opaque()
+    }
+
+    /// Leaves the struct without fields, for types that jitted code only
+    /// ever handles through pointers, such as C's FILE.
+    pub fn keep_opaque(self) -> Struct {
+        let ctx = self.as_type().to_object().get_context();
+        pending(&ctx)
+            .borrow_mut()
+            .0
+            .retain(|&(ptr, _)| ptr != self.ptr as usize);
+        Struct { ptr: self.ptr }
+    }
+
+    /// Used by the Typeable cache, which hands out the struct while its
+    /// fields are still being built.
+    pub(crate) fn as_struct(&self) -> Struct {
+        Struct { ptr: self.ptr }
+    }
+}
+
+impl ToObject for OpaqueStruct {
+    fn to_object(&self) -> Object {
+        let ty = self.as_type();
+        ty.to_object()
+    }
+}
+
+impl fmt::Debug for OpaqueStruct {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let obj = self.as_type();
+        obj.fmt(fmt)
+    }
+}
+
+/// The opaque structs of a context that have not been completed yet, by
+/// address and name.
+#[derive(Default)]
+struct Pending(Vec<(usize, String)>);
+
+fn pending(ctx: &Context) -> Rc<RefCell<Pending>> {
+    ctx::context_state(ctx)
+}
+
+pub(crate) fn new_opaque(ctx: &Context, ptr: *mut gccjit_sys::gcc_jit_struct, name: &str) -> OpaqueStruct {
+    pending(ctx)
+        .borrow_mut()
+        .0
+        .push((ptr as usize, name.to_string()));
+    OpaqueStruct { ptr }
+}
+
+/// Panics if any opaque struct of the context was never completed.
+pub(crate) fn check_complete(ctx: &Context) {
+    let names: Vec<_> = pending(ctx)
+        .borrow()
+        .0
+        .iter()
+        .map(|(_, name)| name.clone())
+        .collect();
+    if !names.is_empty() {
+        panic!(
+            "struct {} declared with new_opaque_struct_type was never completed; \
+             call set_fields or keep_opaque on it before compiling",
+            names.join(", struct ")
+        );
     }
 }
 
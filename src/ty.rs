@@ -1,10 +1,22 @@
 
+use crate::cdecl::{self, Shape};
 use crate::ctx::*;
+use crate::field::Field;
+use crate::structs::Struct;
 use crate::sys::*;
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
+use std::hash::Hash;
+use std::ptr::NonNull;
 
+pub use gccjit_derive::Typeable;
 
-#[derive(Copy, Clone)]
+/// Types compare equal when they are the same gccjit object. Basic types
+/// and pointers to a given type are only created once per context, but
+/// e.g. two calls to make_const() produce two different objects.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Type {
     ptr: *mut gcc_jit_type,
 }
@@ -12,12 +24,16 @@ pub struct Type {
 impl Type {
     /// Given a type T, creates a type to *T, a pointer to T.
     pub fn make_pointer(self) -> Type {
-        unsafe { from_ptr(gccjit_sys::gcc_jit_type_get_pointer(self.ptr)) }
+        let ty = unsafe { from_ptr(gccjit_sys::gcc_jit_type_get_pointer(self.ptr)) };
+        cdecl::record_shape(ty, Shape::Pointer(self));
+        ty
     }
 
     /// Given a type T, creates a type of const T.
     pub fn make_const(self) -> Type {
-        unsafe { from_ptr(gccjit_sys::gcc_jit_type_get_const(self.ptr)) }
+        let ty = unsafe { from_ptr(gccjit_sys::gcc_jit_type_get_const(self.ptr)) };
+        cdecl::record_shape(ty, Shape::Const(self));
+        ty
     }
 
     pub fn from_const(ctx: &Context, u: u32) -> Type {
@@ -27,7 +43,127 @@ impl Type {
     /// Given a type T, creates a new type of volatile T, which
     /// has the semantics of C's volatile.
     pub fn make_volatile(self) -> Type {
-        unsafe { from_ptr(gccjit_sys::gcc_jit_type_get_volatile(self.ptr)) }
+        let ty = unsafe { from_ptr(gccjit_sys::gcc_jit_type_get_volatile(self.ptr)) };
+        cdecl::record_shape(ty, Shape::Volatile(self));
+        ty
+    }
+
+    /// Strips const and volatile from a type, along with any alignment set
+    /// by get_aligned.
+    pub fn unqualified(&self) -> Type {
+        let mut ty = *self;
+        loop {
+            let inner = unsafe { from_ptr(gccjit_sys::gcc_jit_type_unqualified(ty.ptr)) };
+            if inner == ty {
+                return ty;
+            }
+            ty = inner;
+        }
+    }
+
+    /// Returns true if this is a pointer, including a function pointer or
+    /// a qualified pointer such as `int *const`.
+    pub fn is_pointer(&self) -> bool {
+        let ty = self.unqualified();
+        unsafe { !gccjit_sys::gcc_jit_type_is_pointer(ty.ptr).is_null() }
+    }
+
+    /// Gets the type a data pointer points to, looking through qualifiers
+    /// on the pointer itself. Returns None for anything that isn't a
+    /// pointer, and for function pointers, which point to code.
+    pub fn get_pointee(&self) -> Option<Type> {
+        let ty = self.unqualified();
+        unsafe {
+            if !gccjit_sys::gcc_jit_type_dyncast_function_ptr_type(ty.ptr).is_null() {
+                return None;
+            }
+            let pointee = gccjit_sys::gcc_jit_type_is_pointer(ty.ptr);
+            if pointee.is_null() {
+                None
+            } else {
+                Some(from_ptr(pointee))
+            }
+        }
+    }
+
+    /// Returns true if this is one of the unqualified integer types, or bool.
+    pub fn is_integral(&self) -> bool {
+        let ctx = self.to_object().get_context();
+        let kinds = [
+            gcc_jit_types_GCC_JIT_TYPE_BOOL,
+            gcc_jit_types_GCC_JIT_TYPE_CHAR,
+            gcc_jit_types_GCC_JIT_TYPE_SIGNED_CHAR,
+            gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_CHAR,
+            gcc_jit_types_GCC_JIT_TYPE_SHORT,
+            gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_SHORT,
+            gcc_jit_types_GCC_JIT_TYPE_INT,
+            gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_INT,
+            gcc_jit_types_GCC_JIT_TYPE_LONG,
+            gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_LONG,
+            gcc_jit_types_GCC_JIT_TYPE_LONG_LONG,
+            gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_LONG_LONG,
+            gcc_jit_types_GCC_JIT_TYPE_SIZE_T,
+            gcc_jit_types_GCC_JIT_TYPE_INT128_T,
+            gcc_jit_types_GCC_JIT_TYPE_UINT128_T,
+            gcc_jit_types_GCC_JIT_TYPE_UINT8_T,
+            gcc_jit_types_GCC_JIT_TYPE_UINT16_T,
+            gcc_jit_types_GCC_JIT_TYPE_UINT32_T,
+            gcc_jit_types_GCC_JIT_TYPE_UINT64_T,
+            gcc_jit_types_GCC_JIT_TYPE_INT8_T,
+            gcc_jit_types_GCC_JIT_TYPE_INT16_T,
+            gcc_jit_types_GCC_JIT_TYPE_INT32_T,
+            gcc_jit_types_GCC_JIT_TYPE_INT64_T,
+        ];
+        kinds.iter().any(|&kind| *self == Type::from_const(&ctx, kind))
+    }
+
+    /// Returns true if this is a signed integer type. Plain char is taken
+    /// to be signed, as it is on x86.
+    pub fn is_signed(&self) -> bool {
+        let ctx = self.to_object().get_context();
+        let kinds = [
+            gcc_jit_types_GCC_JIT_TYPE_CHAR,
+            gcc_jit_types_GCC_JIT_TYPE_SIGNED_CHAR,
+            gcc_jit_types_GCC_JIT_TYPE_SHORT,
+            gcc_jit_types_GCC_JIT_TYPE_INT,
+            gcc_jit_types_GCC_JIT_TYPE_LONG,
+            gcc_jit_types_GCC_JIT_TYPE_LONG_LONG,
+            gcc_jit_types_GCC_JIT_TYPE_INT128_T,
+            gcc_jit_types_GCC_JIT_TYPE_INT8_T,
+            gcc_jit_types_GCC_JIT_TYPE_INT16_T,
+            gcc_jit_types_GCC_JIT_TYPE_INT32_T,
+            gcc_jit_types_GCC_JIT_TYPE_INT64_T,
+        ];
+        kinds.iter().any(|&kind| *self == Type::from_const(&ctx, kind))
+    }
+
+    /// Gets C's `_Complex long double`, which has no Rust counterpart.
+    pub fn complex_long_double(ctx: &Context) -> Type {
+        Type::from_const(ctx, gcc_jit_types_GCC_JIT_TYPE_COMPLEX_LONG_DOUBLE)
+    }
+
+    /// Returns true if this is one of the unqualified complex
+    /// floating-point types.
+    pub fn is_complex(&self) -> bool {
+        let ctx = self.to_object().get_context();
+        *self == ctx.new_type::<Complex<f32>>()
+            || *self == ctx.new_type::<Complex<f64>>()
+            || *self == Type::complex_long_double(&ctx)
+    }
+}
+
+/// A complex number with the same layout as C's `_Complex float` and
+/// `_Complex double`, which `Complex<f32>` and `Complex<f64>` map to.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Complex<T> {
+    pub re: T,
+    pub im: T,
+}
+
+impl<T> Complex<T> {
+    pub fn new(re: T, im: T) -> Complex<T> {
+        Complex { re, im }
     }
 }
 
@@ -69,6 +205,13 @@ impl fmt::Debug for Type {
     }
 }
 
+/// The signed type as wide as size_t. That is long, except on 64-bit
+/// Windows, where long stays 32 bits wide.
+#[cfg(not(all(windows, target_pointer_width = "64")))]
+const SSIZE_T: u32 = gcc_jit_types_GCC_JIT_TYPE_LONG;
+#[cfg(all(windows, target_pointer_width = "64"))]
+const SSIZE_T: u32 = gcc_jit_types_GCC_JIT_TYPE_LONG_LONG;
+
 typeable_def!((), gcc_jit_types_GCC_JIT_TYPE_VOID);
 typeable_def!(bool, gcc_jit_types_GCC_JIT_TYPE_BOOL);
 typeable_def!(char, gcc_jit_types_GCC_JIT_TYPE_CHAR);
@@ -78,29 +221,221 @@ typeable_def!(i16, gcc_jit_types_GCC_JIT_TYPE_SHORT);
 typeable_def!(u16, gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_SHORT);
 typeable_def!(i32, gcc_jit_types_GCC_JIT_TYPE_INT);
 typeable_def!(u32, gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_INT);
-typeable_def!(i64, gcc_jit_types_GCC_JIT_TYPE_LONG);
-typeable_def!(u64, gcc_jit_types_GCC_JIT_TYPE_UNSIGNED_LONG);
+// Not long: that stays 32 bits wide on 64-bit Windows.
+typeable_def!(i64, gcc_jit_types_GCC_JIT_TYPE_INT64_T);
+typeable_def!(u64, gcc_jit_types_GCC_JIT_TYPE_UINT64_T);
 typeable_def!(f32, gcc_jit_types_GCC_JIT_TYPE_FLOAT);
 typeable_def!(f64, gcc_jit_types_GCC_JIT_TYPE_DOUBLE);
 typeable_def!(usize, gcc_jit_types_GCC_JIT_TYPE_SIZE_T);
+typeable_def!(isize, SSIZE_T);
+typeable_def!(i128, gcc_jit_types_GCC_JIT_TYPE_INT128_T);
+typeable_def!(u128, gcc_jit_types_GCC_JIT_TYPE_UINT128_T);
+typeable_def!(Complex<f32>, gcc_jit_types_GCC_JIT_TYPE_COMPLEX_FLOAT);
+typeable_def!(Complex<f64>, gcc_jit_types_GCC_JIT_TYPE_COMPLEX_DOUBLE);
 
 impl<T: Typeable> Typeable for *mut T {
     fn get_type(ctx: &Context) -> Type {
-        unsafe {
-            let ptr = gcc_jit_type_get_pointer(get_ptr(&T::get_type(ctx)));
-            from_ptr(ptr)
-        }
+        T::get_type(ctx).make_pointer()
     }
 }
 
+/// `*const T` is a pointer to a const T (`const T*`), not a const pointer.
 impl<T: Typeable> Typeable for *const T {
     fn get_type(ctx: &Context) -> Type {
-        unsafe {
-           
-            let ptr = gcc_jit_type_get_pointer(get_ptr(&T::get_type(ctx)));
-            from_ptr(ptr).make_const()
+        const_type(ctx, T::get_type(ctx)).make_pointer()
+    }
+}
+
+impl<T: Typeable> Typeable for &T {
+    fn get_type(ctx: &Context) -> Type {
+        const_type(ctx, T::get_type(ctx)).make_pointer()
+    }
+}
+
+impl<T: Typeable> Typeable for &mut T {
+    fn get_type(ctx: &Context) -> Type {
+        T::get_type(ctx).make_pointer()
+    }
+}
+
+impl<T: Typeable> Typeable for NonNull<T> {
+    fn get_type(ctx: &Context) -> Type {
+        T::get_type(ctx).make_pointer()
+    }
+}
+
+/// `Option<NonNull<T>>` has the same representation as `*mut T`, with
+/// `None` being the null pointer.
+impl<T: Typeable> Typeable for Option<NonNull<T>> {
+    fn get_type(ctx: &Context) -> Type {
+        T::get_type(ctx).make_pointer()
+    }
+}
+
+impl<T: Typeable, const N: usize> Typeable for [T; N] {
+    fn get_type(ctx: &Context) -> Type {
+        let len = match i32::try_from(N) {
+            Ok(len) => len,
+            Err(_) => panic!("gccjit arrays can't have {} elements", N),
+        };
+        let element = T::get_type(ctx);
+        cached(
+            ctx,
+            (element, len),
+            |caches| &mut caches.arrays,
+            || ctx.new_array_type(None, element, len),
+        )
+    }
+}
+
+/// Tuples are mapped to structs with C layout whose fields are named
+/// `_0`, `_1`, and so on. There is one struct per context and list of
+/// element types. Note that Rust does not guarantee that a tuple has the
+/// same layout, so tuples should only be passed by value to jitted code
+/// when the layout is known to agree.
+macro_rules! typeable_tuple {
+    ($name:expr; $($param:ident : $idx:tt),+) => {
+        impl<$($param: Typeable),+> Typeable for ($($param,)+) {
+            fn get_type(ctx: &Context) -> Type {
+                tuple_type(ctx, $name, &[$($param::get_type(ctx)),+])
+            }
         }
+    };
+}
+
+fn tuple_type(ctx: &Context, name: &str, elements: &[Type]) -> Type {
+    let caches = context_state::<TypeCaches>(ctx);
+    if let Some(&ty) = caches.borrow().tuples.get(elements) {
+        return ty;
+    }
+    let fields: Vec<_> = elements
+        .iter()
+        .enumerate()
+        .map(|(i, &ty)| ctx.new_field(None, ty, format!("_{}", i)))
+        .collect();
+    let ty = ctx.new_struct_type(None, name, &fields).as_type();
+    caches.borrow_mut().tuples.insert(elements.to_vec(), ty);
+    ty
+}
+
+typeable_tuple!("tuple1"; A: 0);
+typeable_tuple!("tuple2"; A: 0, B: 1);
+typeable_tuple!("tuple3"; A: 0, B: 1, C: 2);
+typeable_tuple!("tuple4"; A: 0, B: 1, C: 2, D: 3);
+typeable_tuple!("tuple5"; A: 0, B: 1, C: 2, D: 3, E: 4);
+typeable_tuple!("tuple6"; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+typeable_tuple!("tuple7"; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+typeable_tuple!("tuple8"; A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
+
+/// `extern "C"` function pointers are mapped to gccjit function pointer types.
+macro_rules! typeable_fn_ptr {
+    ($($param:ident),*) => {
+        impl<R: Typeable $(, $param: Typeable)*> Typeable for extern "C" fn($($param),*) -> R {
+            fn get_type(ctx: &Context) -> Type {
+                fn_ptr_type(ctx, R::get_type(ctx), vec![$($param::get_type(ctx)),*])
+            }
+        }
+
+        impl<R: Typeable $(, $param: Typeable)*> Typeable for unsafe extern "C" fn($($param),*) -> R {
+            fn get_type(ctx: &Context) -> Type {
+                fn_ptr_type(ctx, R::get_type(ctx), vec![$($param::get_type(ctx)),*])
+            }
+        }
+    };
+}
+
+typeable_fn_ptr!();
+typeable_fn_ptr!(A);
+typeable_fn_ptr!(A, B);
+typeable_fn_ptr!(A, B, C);
+typeable_fn_ptr!(A, B, C, D);
+typeable_fn_ptr!(A, B, C, D, E);
+typeable_fn_ptr!(A, B, C, D, E, F);
+typeable_fn_ptr!(A, B, C, D, E, F, G);
+typeable_fn_ptr!(A, B, C, D, E, F, G, H);
+
+/// The types made for Rust types in a context. A union maps to None while
+/// its fields are being built. gccjit makes a new object for every const,
+/// array and function pointer type asked for, so those are kept too:
+/// otherwise e.g. `&i32` would be a different type on every call, and so
+/// would every tuple, slice or signature containing it.
+#[derive(Default)]
+struct TypeCaches {
+    structs: HashMap<TypeId, Struct>,
+    unions: HashMap<TypeId, Option<Type>>,
+    tuples: HashMap<Vec<Type>, Type>,
+    consts: HashMap<Type, Type>,
+    arrays: HashMap<(Type, i32), Type>,
+    fn_ptrs: HashMap<(Type, Vec<Type>), Type>,
+}
+
+/// Returns the type cached under `key`, making it with `make` on first use.
+fn cached<K: Eq + Hash>(
+    ctx: &Context,
+    key: K,
+    map: impl FnOnce(&mut TypeCaches) -> &mut HashMap<K, Type>,
+    make: impl FnOnce() -> Type,
+) -> Type {
+    let caches = context_state::<TypeCaches>(ctx);
+    let mut caches = caches.borrow_mut();
+    *map(&mut caches).entry(key).or_insert_with(make)
+}
+
+fn const_type(ctx: &Context, ty: Type) -> Type {
+    cached(ctx, ty, |caches| &mut caches.consts, || ty.make_const())
+}
+
+fn fn_ptr_type(ctx: &Context, return_type: Type, params: Vec<Type>) -> Type {
+    let make = || ctx.new_function_pointer_type(None, return_type, &params, false);
+    cached(
+        ctx,
+        (return_type, params.clone()),
+        |caches| &mut caches.fn_ptrs,
+        make,
+    )
+}
+
+/// Returns the struct type that represents the Rust type T in the given
+/// context, creating it on first use. The struct is registered before
+/// `fields` is called, so fields that refer back to T through a pointer
+/// get the same struct. This is what `#[derive(Typeable)]` expands to.
+pub fn struct_type_for<T: 'static>(
+    ctx: &Context,
+    name: &str,
+    fields: impl FnOnce(&Context) -> Vec<Field>,
+) -> Struct {
+    let caches = context_state::<TypeCaches>(ctx);
+    let key = TypeId::of::<T>();
+    if let Some(&s) = caches.borrow().structs.get(&key) {
+        return s;
+    }
+    let opaque = ctx.new_opaque_struct_type(None, name);
+    caches.borrow_mut().structs.insert(key, opaque.as_struct());
+    let fields = fields(ctx);
+    opaque.set_fields(None, &fields)
+}
+
+/// Returns the union type that represents the Rust type T in the given
+/// context, creating it on first use. gccjit can't forward declare unions,
+/// so unlike structs a union can't refer back to itself.
+pub fn union_type_for<T: 'static>(
+    ctx: &Context,
+    name: &str,
+    fields: impl FnOnce(&Context) -> Vec<Field>,
+) -> Type {
+    let caches = context_state::<TypeCaches>(ctx);
+    let key = TypeId::of::<T>();
+    let cached = caches.borrow().unions.get(&key).copied();
+    match cached {
+        Some(Some(ty)) => return ty,
+        Some(None) => panic!("union {} refers to itself, which gccjit can't represent", name),
+        None => {}
     }
+    caches.borrow_mut().unions.insert(key, None);
+    let fields = fields(ctx);
+    let ty = ctx.new_union_type(None, name, &fields).as_type();
+    caches.borrow_mut().unions.insert(key, Some(ty));
+    ty
 }
 
 pub unsafe fn from_ptr(ptr: *mut gccjit_sys::gcc_jit_type) -> Type {
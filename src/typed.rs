@@ -0,0 +1,317 @@
+//! A statically typed layer over RValue and LValue. A `TypedRValue<T>` is an
+//! RValue whose gccjit type is the one that `T` maps to through Typeable,
+//! so operators on it are checked by the Rust compiler: adding an
+//! `TypedRValue<i32>` to a `TypedRValue<f64>` doesn't compile, comparisons
+//! give a `TypedRValue<bool>`, and conversions have to go through `cast`.
+//!
+//! The untyped values are always one call away, through `rvalue()`,
+//! `lvalue()` and the ToRValue and ToLValue impls.
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Add, BitAnd, BitOr, BitXor, Div, Mul, Neg, Not, Rem, Shl, Shr, Sub};
+
+use crate::block::{BinaryOp, Block, ComparisonOp, UnaryOp};
+use crate::cdecl;
+use crate::constant::IntoConst;
+use crate::ctx::Context;
+use crate::location::Location;
+use crate::lvalue::{LValue, ToLValue};
+use crate::object::ToObject;
+use crate::rvalue::{RValue, ToRValue};
+use crate::ty::{Complex, Typeable};
+
+/// Types that support `+`, `-`, `*` and `/`.
+pub trait Numeric: Typeable {}
+
+/// Integer types, which also support `%`, the bitwise operators and shifts.
+pub trait Integer: Numeric {}
+
+/// Types that support unary `-`.
+pub trait Signed: Numeric {}
+
+/// Types that support `==` and `!=`.
+pub trait Comparable: Typeable {}
+
+/// Types that support `<`, `<=`, `>` and `>=`.
+pub trait Ordered: Comparable {}
+
+macro_rules! marker_impls {
+    ($tr:ident: $($ty:ty),*) => {
+        $(impl $tr for $ty {})*
+    };
+}
+
+marker_impls!(Numeric: i8, u8, i16, u16, i32, u32, i64, u64, usize, isize, i128, u128, f32, f64);
+marker_impls!(Numeric: Complex<f32>, Complex<f64>);
+marker_impls!(Integer: i8, u8, i16, u16, i32, u32, i64, u64, usize, isize, i128, u128);
+marker_impls!(Signed: i8, i16, i32, i64, isize, i128, f32, f64, Complex<f32>, Complex<f64>);
+marker_impls!(Comparable: bool, i8, u8, i16, u16, i32, u32, i64, u64, usize, isize, i128, u128, f32, f64);
+marker_impls!(Comparable: Complex<f32>, Complex<f64>);
+marker_impls!(Ordered: i8, u8, i16, u16, i32, u32, i64, u64, usize, isize, i128, u128, f32, f64);
+
+impl<T: Typeable> Comparable for *mut T {}
+impl<T: Typeable> Comparable for *const T {}
+impl<T: Typeable> Ordered for *mut T {}
+impl<T: Typeable> Ordered for *const T {}
+
+/// An RValue of the gccjit type that T maps to.
+pub struct TypedRValue<T> {
+    rvalue: RValue,
+    marker: PhantomData<fn() -> T>,
+}
+
+/// An LValue of the gccjit type that T maps to.
+pub struct TypedLValue<T> {
+    lvalue: LValue,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for TypedRValue<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TypedRValue<T> {}
+
+impl<T> Clone for TypedLValue<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TypedLValue<T> {}
+
+impl<T> fmt::Debug for TypedRValue<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.rvalue.fmt(fmt)
+    }
+}
+
+impl<T> fmt::Debug for TypedLValue<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.lvalue.fmt(fmt)
+    }
+}
+
+impl<T> ToRValue for TypedRValue<T> {
+    fn to_rvalue(&self) -> RValue {
+        self.rvalue
+    }
+}
+
+impl<T> ToRValue for TypedLValue<T> {
+    fn to_rvalue(&self) -> RValue {
+        self.lvalue.to_rvalue()
+    }
+}
+
+impl<T> ToLValue for TypedLValue<T> {
+    fn to_lvalue(&self) -> LValue {
+        self.lvalue
+    }
+}
+
+impl<T: Typeable> TypedRValue<T> {
+    /// Wraps an RValue, checking that it has the type T maps to. Returns
+    /// None if it doesn't. Pointer, qualified, array and function pointer
+    /// types are compared by how they were built, but struct types only
+    /// compare equal to themselves, so this only succeeds for structs
+    /// created once per context, such as the ones from
+    /// `#[derive(Typeable)]`.
+    pub fn from_rvalue<R: ToRValue>(value: R) -> Option<TypedRValue<T>> {
+        let rvalue = value.to_rvalue();
+        let ctx = context(rvalue);
+        if cdecl::same_type(rvalue.get_type(), T::get_type(&ctx)) {
+            Some(TypedRValue::unchecked(rvalue))
+        } else {
+            None
+        }
+    }
+
+    /// Wraps an RValue without checking its type.
+    pub fn unchecked<R: ToRValue>(value: R) -> TypedRValue<T> {
+        TypedRValue {
+            rvalue: value.to_rvalue(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Gets the untyped RValue.
+    pub fn rvalue(&self) -> RValue {
+        self.rvalue
+    }
+
+    /// Converts the value to another type, like a C cast.
+    pub fn cast<U: Typeable>(self, loc: Option<Location>) -> TypedRValue<U> {
+        let ctx = context(self.rvalue);
+        TypedRValue::unchecked(ctx.new_cast(loc, self.rvalue, U::get_type(&ctx)))
+    }
+
+    fn unary(self, op: UnaryOp) -> TypedRValue<T> {
+        let ctx = context(self.rvalue);
+        TypedRValue::unchecked(ctx.new_unary_op(None, op, T::get_type(&ctx), self.rvalue))
+    }
+
+    fn binary(self, op: BinaryOp, rhs: RValue) -> TypedRValue<T> {
+        let ctx = context(self.rvalue);
+        TypedRValue::unchecked(ctx.new_binary_op(None, op, T::get_type(&ctx), self.rvalue, rhs))
+    }
+
+    fn compare(self, op: ComparisonOp, rhs: TypedRValue<T>) -> TypedRValue<bool> {
+        let ctx = context(self.rvalue);
+        TypedRValue::unchecked(ctx.new_comparison(None, op, self.rvalue, rhs.rvalue))
+    }
+}
+
+impl<T: Typeable + IntoConst> TypedRValue<T> {
+    /// Creates a constant.
+    pub fn new_const(ctx: &Context, value: T) -> TypedRValue<T> {
+        TypedRValue::unchecked(ctx.new_const(value))
+    }
+}
+
+impl<T: Comparable> TypedRValue<T> {
+    pub fn eq(self, rhs: TypedRValue<T>) -> TypedRValue<bool> {
+        self.compare(ComparisonOp::Equals, rhs)
+    }
+
+    pub fn ne(self, rhs: TypedRValue<T>) -> TypedRValue<bool> {
+        self.compare(ComparisonOp::NotEquals, rhs)
+    }
+}
+
+impl<T: Ordered> TypedRValue<T> {
+    pub fn lt(self, rhs: TypedRValue<T>) -> TypedRValue<bool> {
+        self.compare(ComparisonOp::LessThan, rhs)
+    }
+
+    pub fn le(self, rhs: TypedRValue<T>) -> TypedRValue<bool> {
+        self.compare(ComparisonOp::LessThanEquals, rhs)
+    }
+
+    pub fn gt(self, rhs: TypedRValue<T>) -> TypedRValue<bool> {
+        self.compare(ComparisonOp::GreaterThan, rhs)
+    }
+
+    pub fn ge(self, rhs: TypedRValue<T>) -> TypedRValue<bool> {
+        self.compare(ComparisonOp::GreaterThanEquals, rhs)
+    }
+}
+
+impl TypedRValue<bool> {
+    /// C's short-circuiting `&&`.
+    pub fn and(self, rhs: TypedRValue<bool>) -> TypedRValue<bool> {
+        self.binary(BinaryOp::LogicalAnd, rhs.rvalue)
+    }
+
+    /// C's short-circuiting `||`.
+    pub fn or(self, rhs: TypedRValue<bool>) -> TypedRValue<bool> {
+        self.binary(BinaryOp::LogicalOr, rhs.rvalue)
+    }
+}
+
+/// `!` is logical negation on bools.
+impl Not for TypedRValue<bool> {
+    type Output = TypedRValue<bool>;
+
+    fn not(self) -> TypedRValue<bool> {
+        self.unary(UnaryOp::LogicalNegate)
+    }
+}
+
+/// `!` is bitwise negation on integers, as in Rust.
+impl<T: Integer> Not for TypedRValue<T> {
+    type Output = TypedRValue<T>;
+
+    fn not(self) -> TypedRValue<T> {
+        self.unary(UnaryOp::BitwiseNegate)
+    }
+}
+
+impl<T: Signed> Neg for TypedRValue<T> {
+    type Output = TypedRValue<T>;
+
+    fn neg(self) -> TypedRValue<T> {
+        self.unary(UnaryOp::Minus)
+    }
+}
+
+macro_rules! typed_binary_operator_for {
+    ($tr:ident, $name:ident, $bound:ident, $op:expr) => {
+        impl<T: $bound> $tr for TypedRValue<T> {
+            type Output = TypedRValue<T>;
+
+            fn $name(self, rhs: TypedRValue<T>) -> TypedRValue<T> {
+                self.binary($op, rhs.rvalue)
+            }
+        }
+    };
+}
+
+typed_binary_operator_for!(Add, add, Numeric, BinaryOp::Plus);
+typed_binary_operator_for!(Sub, sub, Numeric, BinaryOp::Minus);
+typed_binary_operator_for!(Mul, mul, Numeric, BinaryOp::Mult);
+typed_binary_operator_for!(Div, div, Numeric, BinaryOp::Divide);
+typed_binary_operator_for!(Rem, rem, Integer, BinaryOp::Modulo);
+typed_binary_operator_for!(BitAnd, bitand, Integer, BinaryOp::BitwiseAnd);
+typed_binary_operator_for!(BitOr, bitor, Integer, BinaryOp::BitwiseOr);
+typed_binary_operator_for!(BitXor, bitxor, Integer, BinaryOp::BitwiseXor);
+typed_binary_operator_for!(Shl, shl, Integer, BinaryOp::LShift);
+typed_binary_operator_for!(Shr, shr, Integer, BinaryOp::RShift);
+
+impl<T: Typeable> TypedRValue<*mut T> {
+    /// Given a pointer p, returns the LValue *p.
+    pub fn dereference(self, loc: Option<Location>) -> TypedLValue<T> {
+        TypedLValue::unchecked(self.rvalue.dereference(loc))
+    }
+}
+
+impl<T: Typeable> TypedRValue<*const T> {
+    /// Given a pointer p, returns the LValue *p. Writing to it is refused
+    /// by gccjit, since the pointee is const.
+    pub fn dereference(self, loc: Option<Location>) -> TypedLValue<T> {
+        TypedLValue::unchecked(self.rvalue.dereference(loc))
+    }
+}
+
+impl<T: Typeable> TypedLValue<T> {
+    /// Wraps an LValue, checking that it has the type T maps to. Returns
+    /// None if it doesn't.
+    pub fn from_lvalue<L: ToLValue>(value: L) -> Option<TypedLValue<T>> {
+        let lvalue = value.to_lvalue();
+        TypedRValue::<T>::from_rvalue(lvalue).map(|_| TypedLValue::unchecked(lvalue))
+    }
+
+    /// Wraps an LValue without checking its type.
+    pub fn unchecked<L: ToLValue>(value: L) -> TypedLValue<T> {
+        TypedLValue {
+            lvalue: value.to_lvalue(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Gets the untyped LValue.
+    pub fn lvalue(&self) -> LValue {
+        self.lvalue
+    }
+
+    /// Loads the value.
+    pub fn get(&self) -> TypedRValue<T> {
+        TypedRValue::unchecked(self.lvalue.to_rvalue())
+    }
+
+    /// Given an LValue x, returns &x.
+    pub fn get_address(&self, loc: Option<Location>) -> TypedRValue<*mut T> {
+        TypedRValue::unchecked(self.lvalue.get_address(loc))
+    }
+
+    /// Adds `self = value` to the block.
+    pub fn assign(&self, block: Block, loc: Option<Location>, value: TypedRValue<T>) {
+        block.add_assignment(loc, self.lvalue, value.rvalue);
+    }
+}
+
+fn context(rvalue: RValue) -> Context {
+    rvalue.to_object().get_context()
+}
@@ -0,0 +1,274 @@
+use gccjit_rs::*;
+
+use arith::RustArith;
+use block::{BinaryOp, Block};
+use ctx::*;
+use function::*;
+use rvalue::{RValue, ToRValue};
+use ty::Type;
+
+mod common;
+
+/// Defines `ty name(ty a, ty b)`, which returns what `body` builds from the
+/// entry block and the parameters, in the block `body` returns.
+fn binary<F>(ctx: &Context, name: &str, ty: Type, body: F)
+where
+    F: FnOnce(Block, RValue, RValue) -> (RValue, Block),
+{
+    let a = ctx.new_parameter(None, ty, "a");
+    let b = ctx.new_parameter(None, ty, "b");
+    let func = ctx.new_function(None, FunctionType::Exported, ty, &[a, b], name, false);
+    let a = func.get_param(0).to_rvalue();
+    let b = func.get_param(1).to_rvalue();
+    let (value, block) = body(func.new_block("entry"), a, b);
+    block.end_with_return(None, value);
+}
+
+#[test]
+fn overflowing_ops_use_infinite_precision() {
+    // signed char mul(signed char a, signed char b), wrapping
+    // bool mul_overflows(signed char a, signed char b)
+    // bool sum_overflows_u8(int a, int b), for a + b as an unsigned char
+    let ctx = Context::default();
+    let i8_ty = ctx.new_type::<i8>();
+    let int = ctx.new_type::<i32>();
+    let boolean = ctx.new_type::<bool>();
+    let function = |name: &str, ty: Type, op: BinaryOp, result_type: Type, flag: bool| {
+        let a = ctx.new_parameter(None, ty, "a");
+        let b = ctx.new_parameter(None, ty, "b");
+        let ret = if flag { boolean } else { result_type };
+        let func = ctx.new_function(None, FunctionType::Exported, ret, &[a, b], name, false);
+        let block = func.new_block("entry");
+        let (result, overflowed) = ctx.new_overflowing_op(
+            block,
+            None,
+            op,
+            func.get_param(0),
+            func.get_param(1),
+            result_type,
+        );
+        block.end_with_return(None, if flag { overflowed } else { result });
+    };
+    function("mul", i8_ty, BinaryOp::Mult, i8_ty, false);
+    function("mul_overflows", i8_ty, BinaryOp::Mult, i8_ty, true);
+    function(
+        "sum_overflows_u8",
+        int,
+        BinaryOp::Plus,
+        ctx.new_type::<u8>(),
+        true,
+    );
+
+    let result = ctx.compile();
+    let mul: extern "C" fn(i8, i8) -> i8 =
+        unsafe { std::mem::transmute(result.get_function("mul")) };
+    let mul_overflows: extern "C" fn(i8, i8) -> bool =
+        unsafe { std::mem::transmute(result.get_function("mul_overflows")) };
+    let sum_overflows_u8: extern "C" fn(i32, i32) -> bool =
+        unsafe { std::mem::transmute(result.get_function("sum_overflows_u8")) };
+    assert_eq!(mul(100, 2), 100i8.wrapping_mul(2));
+    assert!(mul_overflows(100, 2));
+    assert!(!mul_overflows(-64, 2));
+    assert!(mul_overflows(i8::MIN, -1));
+    assert!(!sum_overflows_u8(200, 55));
+    assert!(sum_overflows_u8(200, 56));
+    assert!(sum_overflows_u8(-1, 0));
+}
+
+#[test]
+fn checked_ops_branch_on_overflow() {
+    // int add(int a, int b) { checked a + b, trapping on overflow }
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+    binary(&ctx, "add", int, |entry, a, b| {
+        let func = entry.get_function();
+        let trap = func.new_block("trap");
+        let ok = func.new_block("ok");
+        let sum = ctx.new_checked_op(entry, None, BinaryOp::Plus, a, b, int, trap, ok);
+        let trap_fn = ctx.get_builtin_function("__builtin_trap");
+        trap.add_eval(None, ctx.new_call(None, trap_fn, &[]));
+        trap.end_with_return(None, ctx.new_rvalue_zero(int));
+        (sum, ok)
+    });
+
+    let result = ctx.compile();
+    let add: extern "C" fn(i32, i32) -> i32 =
+        unsafe { std::mem::transmute(result.get_function("add")) };
+    assert_eq!(add(i32::MAX - 1, 1), i32::MAX);
+    assert!(common::traps("checked_ops_branch_on_overflow", || {
+        add(i32::MAX, 1);
+    }));
+}
+
+#[test]
+fn wrapping_arithmetic() {
+    let ctx = Context::default();
+    let arith = RustArith::new(&ctx);
+    let i8_ty = ctx.new_type::<i8>();
+    binary(&ctx, "add", i8_ty, |block, a, b| {
+        (arith.wrapping_add(None, a, b), block)
+    });
+    binary(&ctx, "sub", i8_ty, |block, a, b| {
+        (arith.wrapping_sub(None, a, b), block)
+    });
+    binary(&ctx, "mul", i8_ty, |block, a, b| {
+        (arith.wrapping_mul(None, a, b), block)
+    });
+    binary(&ctx, "neg", i8_ty, |block, a, _| {
+        (arith.wrapping_neg(None, a), block)
+    });
+
+    let result = ctx.compile();
+    let get = |name| -> extern "C" fn(i8, i8) -> i8 {
+        unsafe { std::mem::transmute(result.get_function(name)) }
+    };
+    let (add, sub, mul, neg) = (get("add"), get("sub"), get("mul"), get("neg"));
+    assert_eq!(add(i8::MAX, 1), i8::MIN);
+    assert_eq!(add(-100, -100), (-100i8).wrapping_add(-100));
+    assert_eq!(sub(i8::MIN, 1), i8::MAX);
+    assert_eq!(mul(16, 16), 0);
+    assert_eq!(mul(-128, -1), i8::MIN);
+    assert_eq!(neg(i8::MIN, 0), i8::MIN);
+    assert_eq!(neg(5, 0), -5);
+}
+
+#[test]
+fn shift_amounts_are_masked() {
+    // unsigned shl(unsigned a, unsigned n) { return a << (n & 31); }
+    // int shr(int a, int n) { return a >> (n & 31); }
+    // signed char shl_i8(signed char a, signed char n) { ... (n & 7) ... }
+    let ctx = Context::default();
+    let arith = RustArith::new(&ctx);
+    binary(&ctx, "shl", ctx.new_type::<u32>(), |block, a, n| {
+        (arith.wrapping_shl(None, a, n), block)
+    });
+    binary(&ctx, "shr", ctx.new_type::<i32>(), |block, a, n| {
+        (arith.wrapping_shr(None, a, n), block)
+    });
+    binary(&ctx, "shl_i8", ctx.new_type::<i8>(), |block, a, n| {
+        (arith.wrapping_shl(None, a, n), block)
+    });
+
+    let result = ctx.compile();
+    let shl: extern "C" fn(u32, u32) -> u32 =
+        unsafe { std::mem::transmute(result.get_function("shl")) };
+    let shr: extern "C" fn(i32, i32) -> i32 =
+        unsafe { std::mem::transmute(result.get_function("shr")) };
+    let shl_i8: extern "C" fn(i8, i8) -> i8 =
+        unsafe { std::mem::transmute(result.get_function("shl_i8")) };
+    assert_eq!(shl(1, 32), 1u32.wrapping_shl(32));
+    assert_eq!(shl(1, 33), 2);
+    assert_eq!(shl(3, 31), 3u32.wrapping_shl(31));
+    assert_eq!(shr(-8, 32), -8);
+    assert_eq!(shr(-8, 33), -4);
+    assert_eq!(shl_i8(1, 7), i8::MIN);
+    assert_eq!(shl_i8(1, 8), 1);
+}
+
+#[test]
+fn saturating_arithmetic_stops_at_the_bounds() {
+    let ctx = Context::default();
+    let arith = RustArith::new(&ctx);
+    let i8_ty = ctx.new_type::<i8>();
+    let u8_ty = ctx.new_type::<u8>();
+    binary(&ctx, "add_i8", i8_ty, |block, a, b| {
+        (arith.saturating_add(block, None, a, b), block)
+    });
+    binary(&ctx, "sub_i8", i8_ty, |block, a, b| {
+        (arith.saturating_sub(block, None, a, b), block)
+    });
+    binary(&ctx, "add_u8", u8_ty, |block, a, b| {
+        (arith.saturating_add(block, None, a, b), block)
+    });
+    binary(&ctx, "sub_u8", u8_ty, |block, a, b| {
+        (arith.saturating_sub(block, None, a, b), block)
+    });
+    binary(&ctx, "mul_i32", ctx.new_type::<i32>(), |block, a, b| {
+        (arith.saturating_mul(block, None, a, b), block)
+    });
+
+    let result = ctx.compile();
+    let add_i8: extern "C" fn(i8, i8) -> i8 =
+        unsafe { std::mem::transmute(result.get_function("add_i8")) };
+    let sub_i8: extern "C" fn(i8, i8) -> i8 =
+        unsafe { std::mem::transmute(result.get_function("sub_i8")) };
+    let add_u8: extern "C" fn(u8, u8) -> u8 =
+        unsafe { std::mem::transmute(result.get_function("add_u8")) };
+    let sub_u8: extern "C" fn(u8, u8) -> u8 =
+        unsafe { std::mem::transmute(result.get_function("sub_u8")) };
+    let mul_i32: extern "C" fn(i32, i32) -> i32 =
+        unsafe { std::mem::transmute(result.get_function("mul_i32")) };
+    assert_eq!(add_i8(i8::MAX, 1), i8::MAX);
+    assert_eq!(add_i8(i8::MIN, -1), i8::MIN);
+    assert_eq!(add_i8(i8::MAX - 1, 1), i8::MAX);
+    assert_eq!(sub_i8(i8::MIN, 1), i8::MIN);
+    assert_eq!(sub_i8(i8::MAX, -1), i8::MAX);
+    assert_eq!(add_u8(u8::MAX, 1), u8::MAX);
+    assert_eq!(add_u8(200, 55), 255);
+    assert_eq!(sub_u8(0, 1), 0);
+    assert_eq!(sub_u8(1, 1), 0);
+    assert_eq!(mul_i32(i32::MIN, -1), i32::MAX);
+    assert_eq!(mul_i32(i32::MAX, 2), i32::MAX);
+    assert_eq!(mul_i32(i32::MIN, 2), i32::MIN);
+    assert_eq!(mul_i32(-7, 6), -42);
+}
+
+/// Compiles `signed char div(signed char a, signed char b)` and `rem`,
+/// which trap like Rust's `/` and `%`.
+fn compile_division(ctx: &Context) -> CompileResult {
+    let arith = RustArith::new(ctx);
+    let i8_ty = ctx.new_type::<i8>();
+    binary(ctx, "div", i8_ty, |block, a, b| {
+        arith.div(block, None, a, b)
+    });
+    binary(ctx, "rem", i8_ty, |block, a, b| {
+        arith.rem(block, None, a, b)
+    });
+    ctx.compile()
+}
+
+#[test]
+fn division_rounds_towards_zero() {
+    let ctx = Context::default();
+    let result = compile_division(&ctx);
+    let div: extern "C" fn(i8, i8) -> i8 =
+        unsafe { std::mem::transmute(result.get_function("div")) };
+    let rem: extern "C" fn(i8, i8) -> i8 =
+        unsafe { std::mem::transmute(result.get_function("rem")) };
+    assert_eq!((div(-7, 2), rem(-7, 2)), (-3, -1));
+    assert_eq!((div(i8::MIN, 1), rem(i8::MIN, 1)), (i8::MIN, 0));
+    assert_eq!(div(i8::MAX, -1), -i8::MAX);
+}
+
+#[test]
+fn dividing_min_by_minus_one_traps() {
+    let ctx = Context::default();
+    let result = compile_division(&ctx);
+    let div: extern "C" fn(i8, i8) -> i8 =
+        unsafe { std::mem::transmute(result.get_function("div")) };
+    assert!(common::traps("dividing_min_by_minus_one_traps", || {
+        div(i8::MIN, -1);
+    }));
+}
+
+#[test]
+fn remainder_of_min_by_minus_one_traps() {
+    let ctx = Context::default();
+    let result = compile_division(&ctx);
+    let rem: extern "C" fn(i8, i8) -> i8 =
+        unsafe { std::mem::transmute(result.get_function("rem")) };
+    assert!(common::traps("remainder_of_min_by_minus_one_traps", || {
+        rem(i8::MIN, -1);
+    }));
+}
+
+#[test]
+fn dividing_by_zero_traps() {
+    let ctx = Context::default();
+    let result = compile_division(&ctx);
+    let div: extern "C" fn(i8, i8) -> i8 =
+        unsafe { std::mem::transmute(result.get_function("div")) };
+    assert!(common::traps("dividing_by_zero_traps", || {
+        div(1, 0);
+    }));
+}
@@ -0,0 +1,59 @@
+use gccjit_rs::*;
+
+use atomics::Atomic;
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+use std::sync::atomic::Ordering;
+
+#[test]
+fn void_pointers_are_atomic() {
+    // void *slot;
+    // void *replace(void *p) { return __atomic_exchange_n(&slot, p, __ATOMIC_ACQ_REL); }
+    let ctx = Context::default();
+    let void_ptr = ctx.new_type::<()>().make_pointer();
+    let slot = ctx.new_global(None, GlobalKind::Exported, void_ptr, "slot");
+    let param = ctx.new_parameter(None, void_ptr, "p");
+    let func = ctx.new_function(
+        None,
+        FunctionType::Exported,
+        void_ptr,
+        &[param],
+        "replace",
+        false,
+    );
+    let block = func.new_block("entry");
+    // The function is still open while the atomic measures the pointer.
+    let slot = Atomic::new(slot);
+    let old = slot.exchange(None, func.get_param(0).to_rvalue(), Ordering::AcqRel);
+    block.end_with_return(None, old);
+
+    let result = ctx.compile();
+    let replace: extern "C" fn(*mut u8) -> *mut u8 =
+        unsafe { std::mem::transmute(result.get_function("replace")) };
+    let mut byte = 0u8;
+    assert!(replace(&mut byte).is_null());
+    assert_eq!(replace(std::ptr::null_mut()), &mut byte as *mut u8);
+}
+
+#[test]
+fn volatile_integers_are_atomic() {
+    // volatile int counter;
+    // int bump(int n) { return __atomic_fetch_add(&counter, n, __ATOMIC_SEQ_CST) + n; }
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+    let counter = ctx.new_global(None, GlobalKind::Exported, int.make_volatile(), "counter");
+    let param = ctx.new_parameter(None, int, "n");
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[param], "bump", false);
+    let block = func.new_block("entry");
+    let counter = Atomic::new(counter);
+    let old = counter.fetch_add(None, func.get_param(0).to_rvalue(), Ordering::SeqCst);
+    assert_eq!(old.get_type(), int);
+    block.end_with_return(None, old + func.get_param(0).to_rvalue());
+
+    let result = ctx.compile();
+    let bump: extern "C" fn(i32) -> i32 =
+        unsafe { std::mem::transmute(result.get_function("bump")) };
+    assert_eq!(bump(2), 2);
+    assert_eq!(bump(3), 5);
+}
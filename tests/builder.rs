@@ -0,0 +1,57 @@
+use gccjit_rs::*;
+
+use builder::FunctionBuilder;
+use ctx::*;
+use function::*;
+use lvalue::ToLValue;
+
+#[test]
+fn blocks_ended_by_hand_are_unreachable() {
+    // int answer(void) { return 42; }
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[], "answer", false);
+    let builder = FunctionBuilder::new(func);
+    builder
+        .block()
+        .end_with_return(None, ctx.new_rvalue_from_int(int, 42));
+    assert!(!builder.is_reachable());
+    builder.finish();
+
+    let result = ctx.compile();
+    let answer: extern "C" fn() -> i32 =
+        unsafe { std::mem::transmute(result.get_function("answer")) };
+    assert_eq!(answer(), 42);
+}
+
+#[test]
+fn builders_restore_the_current_block() {
+    // int twice(int x) { x += x; return x; }
+    // void nothing(void) {}
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+    let param = ctx.new_parameter(None, int, "x");
+    let twice = ctx.new_function(None, FunctionType::Exported, int, &[param], "twice", false);
+    let mut builder = FunctionBuilder::new(twice);
+
+    let nothing = ctx.new_function(
+        None,
+        FunctionType::Exported,
+        ctx.new_type::<()>(),
+        &[],
+        "nothing",
+        false,
+    );
+    FunctionBuilder::new(nothing).finish();
+
+    let mut x = twice.get_param(0).to_lvalue();
+    x += x;
+    builder.return_(None, x);
+    builder.finish();
+    assert!(ctx.current_block().is_none());
+
+    let result = ctx.compile();
+    let twice: extern "C" fn(i32) -> i32 =
+        unsafe { std::mem::transmute(result.get_function("twice")) };
+    assert_eq!(twice(21), 42);
+}
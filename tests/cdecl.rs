@@ -0,0 +1,106 @@
+use gccjit_rs::*;
+
+use cdecl::CDecl;
+use ctx::*;
+use function::*;
+
+#[test]
+fn renders_c_declarations() {
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+    let char_ptr = ctx.new_type::<*const char>();
+
+    let handler = ctx.new_function_pointer_type(None, int, &[char_ptr], false);
+    let table = ctx.new_array_type(None, handler, 4);
+
+    let name = ctx.new_field(None, char_ptr, "name");
+    let handlers = ctx.new_field(None, table, "handlers");
+    let flags = ctx.new_bitfield(None, ctx.new_type::<u32>(), 3, "flags");
+    let registry = ctx.new_struct_type(None, "registry", &[name, handlers, flags]);
+
+    let lookup = ctx.new_function(
+        None,
+        FunctionType::Extern,
+        handler,
+        &[
+            ctx.new_parameter(None, registry.as_type().make_pointer(), "reg"),
+            ctx.new_parameter(None, char_ptr, "name"),
+        ],
+        "lookup",
+        false,
+    );
+    let global = ctx.new_global(None, GlobalKind::External, table.make_pointer(), "current");
+
+    assert_eq!(handler.c_decl(), "int (*)(const char *)");
+    assert_eq!(table.c_decl(), "int (*[4])(const char *)");
+    assert_eq!(
+        registry.c_decl(),
+        "struct registry {\n    \
+         const char *name;\n    \
+         int (*handlers[4])(const char *);\n    \
+         unsigned int flags : 3;\n\
+         };"
+    );
+    assert_eq!(
+        lookup.c_decl(),
+        "extern int (*lookup(struct registry *reg, const char *name))(const char *);"
+    );
+    assert_eq!(
+        global.c_decl(),
+        "extern int (*(*current)[4])(const char *);"
+    );
+}
+
+#[test]
+fn renders_qualified_pointers() {
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+
+    assert_eq!(int.make_const().make_pointer().c_decl(), "const int *");
+    assert_eq!(int.make_pointer().make_const().c_decl(), "int * const");
+    assert_eq!(
+        int.make_pointer().make_volatile().make_pointer().c_decl(),
+        "int * volatile *"
+    );
+}
+
+#[test]
+fn qualified_bit_fields() {
+    let ctx = Context::default();
+    let status_ty = ctx.new_type::<u32>().make_volatile();
+    let status = ctx.new_bitfield(None, status_ty, 4, "status");
+    let device = ctx.new_struct_type(None, "device", &[status]);
+
+    assert_eq!(
+        device.c_decl(),
+        "struct device {\n    volatile unsigned int status : 4;\n};"
+    );
+    assert_eq!(layout::size_of(device.as_type()), 4);
+}
+
+#[test]
+fn fields_are_found_by_name() {
+    let ctx = Context::default();
+    let byte = ctx.new_field(None, ctx.new_type::<u8>(), "byte");
+    let word = ctx.new_field(None, ctx.new_type::<u64>(), "word");
+    let bits = ctx.new_union_type(None, "bits", &[byte, word]);
+
+    assert_eq!(bits.fields().len(), 2);
+    assert_eq!(unsafe { field::get_ptr(&bits.field("word")) }, unsafe {
+        field::get_ptr(&word)
+    });
+    assert_eq!(
+        bits.as_type().c_decl(),
+        "union bits {\n    unsigned char byte;\n    uint64_t word;\n};"
+    );
+}
+
+#[test]
+#[should_panic(expected = "has no field `half`; its fields are: byte, word")]
+fn unknown_fields_list_the_known_ones() {
+    let ctx = Context::default();
+    let byte = ctx.new_field(None, ctx.new_type::<u8>(), "byte");
+    let word = ctx.new_field(None, ctx.new_type::<u64>(), "word");
+    ctx.new_union_type(None, "bits", &[byte, word])
+        .field("half");
+}
@@ -0,0 +1,20 @@
+use std::env;
+use std::process::Command;
+
+const TRAP_TEST: &str = "GCCJIT_TRAP_TEST";
+
+/// Returns true if `run` traps. A trap kills the whole process, so the test
+/// named `test` is run again in a child process, where `run` is called; the
+/// child exits cleanly if `run` returns.
+pub fn traps<F: FnOnce()>(test: &str, run: F) -> bool {
+    if env::var(TRAP_TEST).ok().as_deref() == Some(test) {
+        run();
+        std::process::exit(0);
+    }
+    let status = Command::new(env::current_exe().unwrap())
+        .args(&[test, "--exact", "--test-threads=1", "--nocapture"])
+        .env(TRAP_TEST, test)
+        .status()
+        .unwrap();
+    !status.success()
+}
@@ -0,0 +1,95 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use ty::Typeable;
+
+#[derive(Typeable, Copy, Clone)]
+#[repr(C)]
+pub union Word {
+    byte: u8,
+    word: u64,
+}
+
+#[test]
+fn union_globals_keep_every_byte() {
+    let ctx = Context::default();
+    let value = Word {
+        word: 0x0123_4567_89ab_cdef,
+    };
+    ctx.new_global_with_value(None, GlobalKind::Exported, "word", value);
+
+    let result = ctx.compile();
+    let global = result.get_global("word") as *const Word;
+    assert!(!global.is_null());
+    assert_eq!(unsafe { (*global).word }, 0x0123_4567_89ab_cdef);
+}
+
+#[test]
+fn wide_constants() {
+    // uint64_t big(void) { return 0xfedcba9876543210; }
+    // __int128 wide(void) { return -((__int128)1 << 100); }
+    let ctx = Context::default();
+    let u64_ty = ctx.new_type::<u64>();
+    let i128_ty = ctx.new_type::<i128>();
+    let big = ctx.new_function(None, FunctionType::Exported, u64_ty, &[], "big", false);
+    let value = ctx.new_rvalue_from_i128(u64_ty, 0xfedc_ba98_7654_3210);
+    big.new_block("entry").end_with_return(None, value);
+    let wide = ctx.new_function(None, FunctionType::Exported, i128_ty, &[], "wide", false);
+    let value = ctx.new_rvalue_from_i128(i128_ty, -(1 << 100));
+    wide.new_block("entry").end_with_return(None, value);
+
+    let result = ctx.compile();
+    let big: extern "C" fn() -> u64 = unsafe { std::mem::transmute(result.get_function("big")) };
+    let wide: extern "C" fn() -> i128 = unsafe { std::mem::transmute(result.get_function("wide")) };
+    assert_eq!(big(), 0xfedc_ba98_7654_3210);
+    assert_eq!(wide(), -(1 << 100));
+}
+
+#[test]
+#[should_panic(expected = "needs a 128-bit type")]
+fn wide_constants_need_a_wide_type() {
+    let ctx = Context::default();
+    ctx.new_rvalue_from_u128(ctx.new_type::<u64>(), 1 << 64);
+}
+
+#[test]
+#[should_panic(expected = "doesn't fit in")]
+fn large_u64_constants_need_a_64_bit_type() {
+    let ctx = Context::default();
+    ctx.new_rvalue_from_u64(ctx.new_type::<u32>(), u64::MAX);
+}
+
+#[test]
+#[should_panic(expected = "can't be a constant of integer type")]
+fn floats_do_not_truncate_to_integer_constants() {
+    use constant::IntoConst;
+
+    let ctx = Context::default();
+    2.5f64.into_const(&ctx, ctx.new_type::<i32>());
+}
+
+#[test]
+fn sized_integer_types_are_integral() {
+    use sys::*;
+
+    let ctx = Context::default();
+    let int32 = ty::Type::from_const(&ctx, gcc_jit_types_GCC_JIT_TYPE_INT32_T);
+    let uint8 = ty::Type::from_const(&ctx, gcc_jit_types_GCC_JIT_TYPE_UINT8_T);
+    assert!(int32.is_integral() && int32.is_signed());
+    assert!(uint8.is_integral() && !uint8.is_signed());
+    assert_eq!(ctx.common_arithmetic_type(uint8, int32), Some(int32));
+}
+
+#[test]
+fn large_byte_strings() {
+    let ctx = Context::default();
+    let blob: Vec<u8> = (0..1 << 16).map(|i| (i * 7) as u8).collect();
+    ctx.new_byte_string_global(None, GlobalKind::Exported, "blob", &blob, true);
+
+    let result = ctx.compile();
+    let global = result.get_global("blob") as *const u8;
+    let compiled = unsafe { std::slice::from_raw_parts(global, blob.len() + 1) };
+    assert_eq!(&compiled[..blob.len()], &blob[..]);
+    assert_eq!(compiled[blob.len()], 0);
+}
@@ -0,0 +1,119 @@
+use gccjit_rs::*;
+
+use cdecl::CDecl;
+use ctx::*;
+use layout::Layout;
+use std::mem;
+use ty::Typeable;
+
+#[derive(Typeable)]
+#[repr(C)]
+pub struct Point {
+    x: i32,
+    y: f64,
+}
+
+#[derive(Typeable)]
+#[repr(C)]
+pub struct Pair(u8, u16);
+
+#[derive(Typeable)]
+#[repr(C)]
+pub union Bits {
+    f: f32,
+    u: u32,
+    bytes: [u8; 4],
+}
+
+#[derive(Typeable)]
+#[repr(C)]
+pub struct Node {
+    value: i32,
+    next: *mut Node,
+}
+
+#[derive(Typeable)]
+#[repr(i8)]
+pub enum Small {
+    Low = -1,
+    High = 1,
+}
+
+#[derive(Typeable)]
+#[repr(isize)]
+pub enum Offset {
+    Back = -8,
+    Forward = 8,
+}
+
+#[derive(Typeable)]
+#[repr(u128)]
+pub enum Huge {
+    Zero,
+    Max = u128::MAX,
+}
+
+#[derive(Typeable)]
+#[repr(i128)]
+pub enum Signed {
+    Min = i128::MIN,
+}
+
+fn layout_of<T>() -> Layout {
+    Layout {
+        size: mem::size_of::<T>(),
+        align: mem::align_of::<T>(),
+    }
+}
+
+#[test]
+fn structs() {
+    let ctx = Context::default();
+    let point = ctx.new_type::<Point>();
+    assert_eq!(point, ctx.new_type::<Point>());
+    assert_eq!(
+        point.c_decl(),
+        "struct Point {\n    int x;\n    double y;\n};"
+    );
+    assert_eq!(layout::type_layout(point), layout_of::<Point>());
+
+    let pair = ctx.new_type::<Pair>();
+    assert_eq!(
+        pair.c_decl(),
+        "struct Pair {\n    unsigned char _0;\n    unsigned short _1;\n};"
+    );
+    assert_eq!(layout::type_layout(pair), layout_of::<Pair>());
+}
+
+#[test]
+fn unions() {
+    let ctx = Context::default();
+    let bits = ctx.new_type::<Bits>();
+    assert_eq!(bits, ctx.new_type::<Bits>());
+    assert_eq!(
+        bits.c_decl(),
+        "union Bits {\n    float f;\n    unsigned int u;\n    unsigned char bytes[4];\n};"
+    );
+    assert_eq!(layout::type_layout(bits), layout_of::<Bits>());
+}
+
+#[test]
+fn self_referential_structs() {
+    let ctx = Context::default();
+    let node = ctx.new_type::<Node>();
+    assert_eq!(ctx.new_type::<*mut Node>(), node.make_pointer());
+    assert_eq!(
+        node.c_decl(),
+        "struct Node {\n    int value;\n    struct Node *next;\n};"
+    );
+    assert_eq!(layout::type_layout(node), layout_of::<Node>());
+}
+
+#[test]
+fn fieldless_enums_are_their_representation() {
+    let ctx = Context::default();
+    assert_eq!(ctx.new_type::<Small>(), ctx.new_type::<i8>());
+    assert_eq!(ctx.new_type::<Offset>(), ctx.new_type::<isize>());
+    assert_eq!(ctx.new_type::<Huge>(), ctx.new_type::<u128>());
+    assert_eq!(ctx.new_type::<Signed>(), ctx.new_type::<i128>());
+}
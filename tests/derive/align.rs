@@ -0,0 +1,10 @@
+use gccjit_rs::ty::Typeable;
+
+#[derive(Typeable)]
+#[repr(C, align(16))]
+union Block {
+    bytes: [u8; 16],
+    words: [u32; 4],
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use gccjit_rs::ty::Typeable;
+
+#[derive(Typeable)]
+#[repr(u8)]
+enum Shape {
+    Empty,
+    Circle(f64),
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use gccjit_rs::ty::Typeable;
+
+#[derive(Typeable)]
+enum Color {
+    Red,
+    Green,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use gccjit_rs::ty::Typeable;
+
+#[derive(Typeable)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use gccjit_rs::ty::Typeable;
+
+#[derive(Typeable)]
+#[repr(C, packed)]
+struct Header {
+    tag: u8,
+    len: u32,
+}
+
+fn main() {}
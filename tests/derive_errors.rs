@@ -0,0 +1,5 @@
+#[test]
+fn unsupported_types_are_rejected() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/derive/*.rs");
+}
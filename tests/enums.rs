@@ -0,0 +1,40 @@
+use gccjit_rs::*;
+
+use ctx::*;
+
+#[test]
+fn values_cover_the_whole_underlying_type() {
+    let ctx = Context::default();
+    let flags = ctx.new_enum_type(
+        "flags",
+        ctx.new_type::<u64>(),
+        &[("NONE", 0), ("ALL", u64::MAX as i128)],
+    );
+    assert_eq!(flags.value_of("ALL"), Some(u64::MAX as i128));
+    assert_eq!(flags.name_of(u64::MAX as i128), Some("ALL"));
+
+    let wide = ctx.new_enum_type(
+        "wide",
+        ctx.new_type::<i128>(),
+        &[("MIN", i128::MIN), ("MAX", i128::MAX)],
+    );
+    assert_eq!(wide.value_of("MIN"), Some(i128::MIN));
+}
+
+#[test]
+#[should_panic(expected = "value 128 of small::BIG does not fit")]
+fn values_that_do_not_fit_are_rejected() {
+    let ctx = Context::default();
+    ctx.new_enum_type("small", ctx.new_type::<i8>(), &[("BIG", 128)]);
+}
+
+#[test]
+fn variant_constants_show_their_name() {
+    let ctx = Context::default();
+    let opcode = ctx.new_enum_type("opcode", ctx.new_type::<u8>(), &[("ADD", 0), ("HALT", 255)]);
+    assert_eq!(format!("{:?}", opcode.variant("HALT")), "HALT");
+    assert_eq!(
+        format!("{:?}", opcode),
+        "enum opcode : unsigned char { ADD = 0, HALT = 255 }"
+    );
+}
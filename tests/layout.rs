@@ -0,0 +1,108 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use layout::Layout;
+
+#[repr(C)]
+pub struct Header {
+    tag: u8,
+    len: u32,
+    next: *mut Header,
+}
+
+#[test]
+fn struct_layout_matches_rust() {
+    let ctx = Context::default();
+    let header = ctx.new_opaque_struct_type(None, "header");
+    let tag = ctx.new_field(None, ctx.new_type::<u8>(), "tag");
+    let len = ctx.new_field(None, ctx.new_type::<u32>(), "len");
+    let next = ctx.new_field(None, header.as_type().make_pointer(), "next");
+    let header = header.set_fields(None, &[tag, len, next]);
+
+    assert_layout!(Header, header, { tag: tag, len: len, next: next });
+
+    let array = ctx.new_array_type(None, header.as_type(), 3);
+    assert_eq!(
+        layout::type_layout(array),
+        Layout {
+            size: 3 * std::mem::size_of::<Header>(),
+            align: std::mem::align_of::<Header>(),
+        }
+    );
+}
+
+#[test]
+fn bit_fields_start_a_new_unit_instead_of_straddling() {
+    // struct { unsigned char a : 3; unsigned int b : 30; unsigned char c; }
+    let ctx = Context::default();
+    let a = ctx.new_bitfield(None, ctx.new_type::<u8>(), 3, "a");
+    let b = ctx.new_bitfield(None, ctx.new_type::<u32>(), 30, "b");
+    let c = ctx.new_field(None, ctx.new_type::<u8>(), "c");
+    let packed = ctx.new_struct_type(None, "packed", &[a, b, c]);
+
+    let layout = layout::struct_layout(packed, &[c]);
+    assert_eq!((layout.size, layout.align), (12, 4));
+    assert_eq!(layout.offsets, vec![8]);
+}
+
+#[test]
+fn measuring_leaves_the_context_alone() {
+    // size_t address(void *p) { return bit_cast<size_t>(p); }
+    let ctx = Context::default();
+    let usize_ty = ctx.new_type::<usize>();
+    let void_ptr = ctx.new_type::<()>().make_pointer();
+    let param = ctx.new_parameter(None, void_ptr, "p");
+    let func = ctx.new_function(
+        None,
+        FunctionType::Exported,
+        usize_ty,
+        &[param],
+        "address",
+        false,
+    );
+    let block = func.new_block("entry");
+    // Types are measured by probes compiled in a context of their own, so
+    // measuring with a block still open must not disturb this one.
+    assert_eq!(layout::size_of(void_ptr), std::mem::size_of::<usize>());
+    block.end_with_return(None, ctx.new_bitcast(None, param, usize_ty));
+
+    let result = ctx.compile();
+    assert_eq!(ctx.get_first_error(), None);
+    let address: extern "C" fn(*mut u8) -> usize =
+        unsafe { std::mem::transmute(result.get_function("address")) };
+    let mut byte = 0u8;
+    assert_eq!(address(&mut byte), &mut byte as *mut u8 as usize);
+}
+
+#[repr(C)]
+pub struct Outer {
+    flag: bool,
+    inner: Header,
+    value: f64,
+}
+
+#[test]
+fn nested_structs_and_vectors_are_probed() {
+    let ctx = Context::default();
+    let header = ctx.new_opaque_struct_type(None, "header");
+    let tag = ctx.new_field(None, ctx.new_type::<u8>(), "tag");
+    let len = ctx.new_field(None, ctx.new_type::<u32>(), "len");
+    let next = ctx.new_field(None, header.as_type().make_pointer(), "next");
+    let header = header.set_fields(None, &[tag, len, next]);
+    let flag = ctx.new_field(None, ctx.new_type::<bool>(), "flag");
+    let inner = ctx.new_field(None, header.as_type().make_const(), "inner");
+    let value = ctx.new_field(None, ctx.new_type::<f64>(), "value");
+    let outer = ctx.new_struct_type(None, "outer", &[flag, inner, value]);
+
+    assert_layout!(Outer, outer, { flag: flag, inner: inner, value: value });
+
+    let floats = ctx.new_vector_type::<f32>(4);
+    assert_eq!(
+        layout::type_layout(floats),
+        Layout {
+            size: 16,
+            align: 16
+        }
+    );
+}
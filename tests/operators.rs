@@ -0,0 +1,132 @@
+use gccjit_rs::*;
+
+use block::BinaryOp;
+use ctx::*;
+use function::*;
+use lvalue::ToLValue;
+use rvalue::ToRValue;
+
+#[test]
+fn operands_keep_their_type() {
+    let ctx = Context::default();
+    let short = ctx.new_type::<i16>();
+    let x = ctx.new_parameter(None, short, "x").to_rvalue();
+
+    assert_eq!((x + 1).get_type(), short);
+    assert_eq!((1i16 + x).get_type(), short);
+    assert_eq!((x << 2).get_type(), short);
+}
+
+#[test]
+#[should_panic(expected = "can't be an operand of integer type")]
+fn float_literals_do_not_truncate() {
+    let ctx = Context::default();
+    let x = ctx
+        .new_parameter(None, ctx.new_type::<i32>(), "x")
+        .to_rvalue();
+    let _ = x + 2.5;
+}
+
+#[test]
+#[should_panic(expected = "operands have different types")]
+fn mixed_operand_types_panic() {
+    let ctx = Context::default();
+    let x = ctx
+        .new_parameter(None, ctx.new_type::<i32>(), "x")
+        .to_rvalue();
+    let y = ctx
+        .new_parameter(None, ctx.new_type::<i64>(), "y")
+        .to_rvalue();
+    let _ = x + y;
+}
+
+#[test]
+#[should_panic(expected = "needs a current block")]
+fn compound_assignment_needs_a_current_block() {
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[], "f", false);
+    let mut local = func.new_local(None, int, "x");
+    local += 1;
+}
+
+#[test]
+fn compound_assignment_goes_to_the_current_block() {
+    // double scale(double x) { x *= 2.5; return x; }
+    let ctx = Context::default();
+    let double = ctx.new_type::<f64>();
+    let param = ctx.new_parameter(None, double, "x");
+    let func = ctx.new_function(
+        None,
+        FunctionType::Exported,
+        double,
+        &[param],
+        "scale",
+        false,
+    );
+    let block = func.new_block("entry");
+    let mut x = func.get_param(0).to_lvalue();
+
+    ctx.set_current_block(Some(block));
+    x *= 2.5;
+    ctx.set_current_block(None);
+    block.end_with_return(None, x);
+
+    let result = ctx.compile();
+    let scale: extern "C" fn(f64) -> f64 =
+        unsafe { std::mem::transmute(result.get_function("scale")) };
+    assert_eq!(scale(4.0), 10.0);
+}
+
+#[test]
+fn qualifiers_do_not_hide_arithmetic_types() {
+    let ctx = Context::default();
+    let short = ctx.new_type::<i16>();
+    let int = ctx.new_type::<i32>();
+    let int64 = ctx.new_type::<i64>();
+
+    assert_eq!(
+        ctx.common_arithmetic_type(int.make_const(), int64),
+        Some(int64)
+    );
+    assert_eq!(
+        ctx.common_arithmetic_type(short.make_volatile(), short),
+        Some(int)
+    );
+    assert_eq!(
+        ctx.common_arithmetic_type(ctx.new_type::<usize>(), ctx.new_type::<isize>()),
+        Some(ctx.new_type::<usize>())
+    );
+
+    // int64_t add(volatile int a, int64_t b) { return a + b; }
+    let a = ctx.new_parameter(None, int.make_volatile(), "a");
+    let b = ctx.new_parameter(None, int64, "b");
+    let func = ctx.new_function(None, FunctionType::Exported, int64, &[a, b], "add", false);
+    let sum = ctx.new_promoted_binary_op(None, BinaryOp::Plus, a, b);
+    assert_eq!(sum.get_type(), int64);
+    func.new_block("entry").end_with_return(None, sum);
+
+    let result = ctx.compile();
+    let add: extern "C" fn(i32, i64) -> i64 =
+        unsafe { std::mem::transmute(result.get_function("add")) };
+    assert_eq!(add(-1, 1 << 40), (1 << 40) - 1);
+}
+
+#[test]
+fn shifts_take_the_promoted_type_of_the_left_operand() {
+    // int shl(short x, int64_t n) { return x << n; }
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+    let x = ctx.new_parameter(None, ctx.new_type::<i16>(), "x");
+    let n = ctx.new_parameter(None, ctx.new_type::<i64>(), "n");
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[x, n], "shl", false);
+    let shifted = ctx.new_promoted_binary_op(None, BinaryOp::LShift, x, n);
+    assert_eq!(shifted.get_type(), int);
+    func.new_block("entry").end_with_return(None, shifted);
+
+    let result = ctx.compile();
+    let shl: extern "C" fn(i16, i64) -> i32 =
+        unsafe { std::mem::transmute(result.get_function("shl")) };
+    assert_eq!(shl(3, 20), 3 << 20);
+    assert_eq!(shl(-1, 16), -1 << 16);
+}
@@ -0,0 +1,123 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use rvalue::ToRValue;
+use ty::Type;
+
+#[test]
+fn pointer_arithmetic() {
+    // int *offset(int *p, int64_t i) { return p + i; }
+    // int *byte_add(int *p, size_t n) { return (int *)((char *)p + n); }
+    // ptrdiff_t diff(int *p, int *q) { return p - q; }
+    let ctx = Context::default();
+    let int_ptr = ctx.new_type::<*mut i32>();
+    let function = |name: &str, params: &[(Type, &str)], ret: Type| {
+        let params: Vec<_> = params
+            .iter()
+            .map(|&(ty, name)| ctx.new_parameter(None, ty, name))
+            .collect();
+        ctx.new_function(None, FunctionType::Exported, ret, &params, name, false)
+    };
+
+    let offset = function(
+        "offset",
+        &[(int_ptr, "p"), (ctx.new_type::<i64>(), "i")],
+        int_ptr,
+    );
+    let value = offset
+        .get_param(0)
+        .to_rvalue()
+        .ptr_offset(None, offset.get_param(1));
+    assert_eq!(value.get_type(), int_ptr);
+    offset.new_block("entry").end_with_return(None, value);
+
+    let byte_add = function(
+        "byte_add",
+        &[(int_ptr, "p"), (ctx.new_type::<usize>(), "n")],
+        int_ptr,
+    );
+    let value = byte_add
+        .get_param(0)
+        .to_rvalue()
+        .ptr_byte_add(None, byte_add.get_param(1));
+    assert_eq!(value.get_type(), int_ptr);
+    byte_add.new_block("entry").end_with_return(None, value);
+
+    let diff = function(
+        "diff",
+        &[(int_ptr, "p"), (int_ptr, "q")],
+        ctx.new_type::<isize>(),
+    );
+    let value = diff
+        .get_param(0)
+        .to_rvalue()
+        .ptr_diff(None, diff.get_param(1));
+    diff.new_block("entry").end_with_return(None, value);
+
+    let result = ctx.compile();
+    let offset: extern "C" fn(*mut i32, i64) -> *mut i32 =
+        unsafe { std::mem::transmute(result.get_function("offset")) };
+    let byte_add: extern "C" fn(*mut i32, usize) -> *mut i32 =
+        unsafe { std::mem::transmute(result.get_function("byte_add")) };
+    let diff: extern "C" fn(*mut i32, *mut i32) -> isize =
+        unsafe { std::mem::transmute(result.get_function("diff")) };
+
+    let mut values = [0i32; 8];
+    let start = values.as_mut_ptr();
+    let third = unsafe { start.add(3) };
+    assert_eq!(offset(start, 3), third);
+    assert_eq!(offset(third, -2), unsafe { start.add(1) });
+    assert_eq!(byte_add(start, 12), third);
+    assert_eq!(diff(third, start), 3);
+    assert_eq!(diff(start, third), -3);
+}
+
+#[test]
+fn alignment() {
+    // size_t up(size_t n) { return (n + 15) & ~15; }
+    // size_t down(size_t n) { return n & ~15; }
+    // char *up_ptr(char *p) { return (char *)(((size_t)p + 7) & ~7); }
+    let ctx = Context::default();
+    let usize_ty = ctx.new_type::<usize>();
+    let char_ptr = ctx.new_type::<*mut u8>();
+    let function = |name: &str, ty: Type, align: usize, up: bool| {
+        let param = ctx.new_parameter(None, ty, "n");
+        let func = ctx.new_function(None, FunctionType::Exported, ty, &[param], name, false);
+        let param = func.get_param(0).to_rvalue();
+        let value = if up {
+            param.align_up(None, align)
+        } else {
+            param.align_down(None, align)
+        };
+        assert_eq!(value.get_type(), ty);
+        func.new_block("entry").end_with_return(None, value);
+    };
+    function("up", usize_ty, 16, true);
+    function("down", usize_ty, 16, false);
+    function("up_ptr", char_ptr, 8, true);
+
+    let result = ctx.compile();
+    let up: extern "C" fn(usize) -> usize =
+        unsafe { std::mem::transmute(result.get_function("up")) };
+    let down: extern "C" fn(usize) -> usize =
+        unsafe { std::mem::transmute(result.get_function("down")) };
+    let up_ptr: extern "C" fn(*mut u8) -> *mut u8 =
+        unsafe { std::mem::transmute(result.get_function("up_ptr")) };
+
+    assert_eq!((up(0), up(1), up(16), up(17)), (0, 16, 16, 32));
+    assert_eq!((down(0), down(15), down(16), down(31)), (0, 0, 16, 16));
+    let mut bytes = [0u8; 16];
+    let unaligned = (bytes.as_mut_ptr() as usize | 1) as *mut u8;
+    let aligned = up_ptr(unaligned);
+    assert_eq!(aligned as usize % 8, 0);
+    assert!(aligned > unaligned && (aligned as usize) - (unaligned as usize) < 8);
+}
+
+#[test]
+#[should_panic(expected = "not a power of two")]
+fn alignments_must_be_powers_of_two() {
+    let ctx = Context::default();
+    let n = ctx.new_parameter(None, ctx.new_type::<usize>(), "n");
+    n.to_rvalue().align_up(None, 12);
+}
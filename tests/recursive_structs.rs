@@ -0,0 +1,58 @@
+use gccjit_rs::*;
+
+use cdecl::CDecl;
+use ctx::*;
+
+#[test]
+fn mutually_recursive_structs() {
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+
+    let structs = ctx.new_recursive_struct_types(None, &["tree", "node"], |types| {
+        let (tree, node) = (types[0].make_pointer(), types[1].make_pointer());
+        vec![
+            vec![
+                ctx.new_field(None, node, "root"),
+                ctx.new_field(None, int, "size"),
+            ],
+            vec![
+                ctx.new_field(None, tree, "owner"),
+                ctx.new_field(None, node, "left"),
+                ctx.new_field(None, node, "right"),
+                ctx.new_field(None, int, "key"),
+            ],
+        ]
+    });
+
+    assert_eq!(
+        structs[0].c_decl(),
+        "struct tree {\n    struct node *root;\n    int size;\n};"
+    );
+    assert_eq!(
+        structs[1].c_decl(),
+        "struct node {\n    \
+         struct tree *owner;\n    \
+         struct node *left;\n    \
+         struct node *right;\n    \
+         int key;\n\
+         };"
+    );
+}
+
+#[test]
+fn kept_opaque_structs_are_forward_declarations() {
+    let ctx = Context::default();
+    let handle = ctx.new_opaque_struct_type(None, "handle").keep_opaque();
+    assert_eq!(handle.c_decl(), "struct handle;");
+    ctx.compile();
+}
+
+#[test]
+#[should_panic(
+    expected = "struct unfinished declared with new_opaque_struct_type was never completed"
+)]
+fn compiling_with_an_incomplete_struct_panics() {
+    let ctx = Context::default();
+    let _unfinished = ctx.new_opaque_struct_type(None, "unfinished");
+    ctx.compile();
+}
@@ -0,0 +1,103 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use slice::SliceType;
+
+mod common;
+
+#[test]
+fn slice_types_of_composite_elements_are_created_once() {
+    let ctx = Context::default();
+
+    assert_eq!(
+        ctx.new_type::<&[*const u8]>(),
+        ctx.new_type::<&[*const u8]>()
+    );
+    assert_eq!(ctx.new_type::<&[&i32]>(), ctx.new_type::<&[&i32]>());
+    assert_eq!(ctx.new_type::<&[[u8; 4]]>(), ctx.new_type::<&[[u8; 4]]>());
+    assert_eq!(
+        ctx.new_type::<&[extern "C" fn()]>(),
+        ctx.new_type::<&[extern "C" fn()]>()
+    );
+    assert_eq!(ctx.new_type::<&mut [&i32]>(), ctx.new_type::<&[&i32]>());
+}
+
+#[test]
+fn indexing_out_of_bounds_traps() {
+    // unsigned get(slice_unsigned_int s, size_t i) { return s[i]; }
+    let ctx = Context::default();
+    let slice = SliceType::new(&ctx, ctx.new_type::<u32>());
+    let s = ctx.new_parameter(None, slice.as_type(), "s");
+    let i = ctx.new_parameter(None, ctx.new_type::<usize>(), "i");
+    let func = ctx.new_function(
+        None,
+        FunctionType::Exported,
+        ctx.new_type::<u32>(),
+        &[s, i],
+        "get",
+        false,
+    );
+    let (element, block) = slice.index(func.new_block("entry"), None, s, i);
+    block.end_with_return(None, element);
+
+    let result = ctx.compile();
+    let get: extern "C" fn(&[u32], usize) -> u32 =
+        unsafe { std::mem::transmute(result.get_function("get")) };
+    let values = [1, 2, 3];
+    assert_eq!(get(&values, 2), 3);
+    assert!(common::traps("indexing_out_of_bounds_traps", || {
+        get(&values, 3);
+    }));
+}
+
+fn compile_sub_len(ctx: &Context) -> CompileResult {
+    // size_t sub_len(slice_unsigned_int s, size_t start, size_t end)
+    // { return s[start..end].len; }
+    let usize_ty = ctx.new_type::<usize>();
+    let slice = SliceType::new(ctx, ctx.new_type::<u32>());
+    let s = ctx.new_parameter(None, slice.as_type(), "s");
+    let start = ctx.new_parameter(None, usize_ty, "start");
+    let end = ctx.new_parameter(None, usize_ty, "end");
+    let func = ctx.new_function(
+        None,
+        FunctionType::Exported,
+        usize_ty,
+        &[s, start, end],
+        "sub_len",
+        false,
+    );
+    let (sub, block) = slice.subslice(func.new_block("entry"), None, s, start, end);
+    block.end_with_return(None, slice.len(None, sub));
+
+    ctx.compile()
+}
+
+#[test]
+fn subslices_ending_past_the_slice_trap() {
+    let ctx = Context::default();
+    let result = compile_sub_len(&ctx);
+    let sub_len: extern "C" fn(&[u32], usize, usize) -> usize =
+        unsafe { std::mem::transmute(result.get_function("sub_len")) };
+    let values = [1, 2, 3];
+    assert_eq!(sub_len(&values, 1, 3), 2);
+    assert_eq!(sub_len(&values, 3, 3), 0);
+    assert!(common::traps(
+        "subslices_ending_past_the_slice_trap",
+        || {
+            sub_len(&values, 1, 4);
+        }
+    ));
+}
+
+#[test]
+fn reversed_subslices_trap() {
+    let ctx = Context::default();
+    let result = compile_sub_len(&ctx);
+    let sub_len: extern "C" fn(&[u32], usize, usize) -> usize =
+        unsafe { std::mem::transmute(result.get_function("sub_len")) };
+    let values = [1, 2, 3];
+    assert!(common::traps("reversed_subslices_trap", || {
+        sub_len(&values, 2, 1);
+    }));
+}
@@ -0,0 +1,38 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use lvalue::TlsModel;
+use std::thread;
+
+#[test]
+fn each_thread_sees_its_own_copy() {
+    // _Thread_local long counter;
+    // long bump(void) { counter += 1; return counter; }
+    let ctx = Context::default();
+    let long = ctx.new_type::<i64>();
+    let mut counter = ctx.new_global(None, GlobalKind::Exported, long, "counter");
+    counter.set_tls_model(TlsModel::GlobalDynamic);
+    let func = ctx.new_function(None, FunctionType::Exported, long, &[], "bump", false);
+    let block = func.new_block("entry");
+    ctx.set_current_block(Some(block));
+    counter += 1;
+    ctx.set_current_block(None);
+    block.end_with_return(None, counter);
+
+    let result = ctx.compile();
+    let bump: extern "C" fn() -> i64 = unsafe { std::mem::transmute(result.get_function("bump")) };
+    thread::scope(|scope| {
+        for times in 1..=4 {
+            let result = &result;
+            scope.spawn(move || {
+                for count in 1..=times {
+                    assert_eq!(bump(), count);
+                }
+                let counter = result.get_global("counter") as *const i64;
+                assert_eq!(unsafe { *counter }, times);
+            });
+        }
+    });
+    assert_eq!(unsafe { *(result.get_global("counter") as *const i64) }, 0);
+}
@@ -0,0 +1,82 @@
+use gccjit_rs::*;
+
+use cdecl::CDecl;
+use ctx::*;
+use function::*;
+use std::ptr::NonNull;
+
+#[test]
+fn composite_rust_types() {
+    let ctx = Context::default();
+
+    assert_eq!(ctx.new_type::<[u16; 4]>().c_decl(), "unsigned short [4]");
+    assert_eq!(ctx.new_type::<&i32>().c_decl(), "const int *");
+    assert_eq!(ctx.new_type::<&mut i32>().c_decl(), "int *");
+    assert_eq!(
+        ctx.new_type::<Option<NonNull<u8>>>().c_decl(),
+        "unsigned char *"
+    );
+    assert_eq!(
+        ctx.new_type::<extern "C" fn(i32, *const u8) -> u64>()
+            .c_decl(),
+        "uint64_t (*)(int, const unsigned char *)"
+    );
+}
+
+#[test]
+fn tuple_types_are_created_once() {
+    let ctx = Context::default();
+
+    let pair = ctx.new_type::<(i32, f64)>();
+    assert_eq!(pair, ctx.new_type::<(i32, f64)>());
+    assert_ne!(pair, ctx.new_type::<(f64, i32)>());
+    assert_eq!(pair.c_decl(), "struct tuple2");
+
+    // (int, double) pair(int a, double b) { return (tuple2) { a, b }; }
+    let a = ctx.new_parameter(None, ctx.new_type::<i32>(), "a");
+    let b = ctx.new_parameter(None, ctx.new_type::<f64>(), "b");
+    let func = ctx.new_function(None, FunctionType::Exported, pair, &[a, b], "pair", false);
+    let block = func.new_block("entry");
+    let local = func.new_local(None, pair, "result");
+    block.add_assignment(None, local.field(None, "_0"), a);
+    block.add_assignment(None, local.field(None, "_1"), b);
+    block.end_with_return(None, local);
+
+    #[repr(C)]
+    struct Pair(i32, f64);
+    let result = ctx.compile();
+    let pair: extern "C" fn(i32, f64) -> Pair =
+        unsafe { std::mem::transmute(result.get_function("pair")) };
+    let Pair(a, b) = pair(3, 0.5);
+    assert_eq!((a, b), (3, 0.5));
+}
+
+#[test]
+fn composite_types_are_created_once() {
+    let ctx = Context::default();
+
+    assert_eq!(ctx.new_type::<&i32>(), ctx.new_type::<&i32>());
+    assert_eq!(ctx.new_type::<[u8; 4]>(), ctx.new_type::<[u8; 4]>());
+    assert_ne!(ctx.new_type::<[u8; 4]>(), ctx.new_type::<[u8; 8]>());
+    assert_eq!(
+        ctx.new_type::<extern "C" fn(i32)>(),
+        ctx.new_type::<extern "C" fn(i32)>()
+    );
+    assert_eq!(ctx.new_type::<(&i32, i32)>(), ctx.new_type::<(&i32, i32)>());
+    assert_eq!(
+        ctx.new_type::<([u8; 4], *const u8, extern "C" fn())>(),
+        ctx.new_type::<([u8; 4], *const u8, extern "C" fn())>()
+    );
+}
+
+#[test]
+fn isize_is_as_wide_as_usize() {
+    let ctx = Context::default();
+    let isize_ty = ctx.new_type::<isize>();
+    assert!(isize_ty.is_signed());
+    assert_eq!(layout::size_of(isize_ty), std::mem::size_of::<isize>());
+    assert_eq!(
+        layout::size_of(isize_ty),
+        layout::size_of(ctx.new_type::<usize>())
+    );
+}
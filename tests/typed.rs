@@ -0,0 +1,51 @@
+use gccjit_rs::*;
+
+use ctx::*;
+use function::*;
+use typed::{TypedLValue, TypedRValue};
+
+#[test]
+fn reference_parameters_can_be_wrapped() {
+    // int read(const int *p) { return *p; }
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+    let p = ctx.new_parameter(None, ctx.new_type::<&i32>(), "p");
+    let func = ctx.new_function(None, FunctionType::Exported, int, &[p], "read", false);
+    let p = TypedRValue::<&i32>::from_rvalue(func.get_param(0)).unwrap();
+    assert!(TypedRValue::<*const i32>::from_rvalue(p).is_some());
+    assert!(TypedRValue::<*mut i32>::from_rvalue(p).is_none());
+    assert!(TypedRValue::<&u32>::from_rvalue(p).is_none());
+    func.new_block("entry")
+        .end_with_return(None, p.rvalue().dereference(None));
+
+    let result = ctx.compile();
+    let read: extern "C" fn(&i32) -> i32 =
+        unsafe { std::mem::transmute(result.get_function("read")) };
+    assert_eq!(read(&42), 42);
+}
+
+#[test]
+fn composite_types_are_compared_by_shape() {
+    let ctx = Context::default();
+    let int = ctx.new_type::<i32>();
+    let func = ctx.new_function(
+        None,
+        FunctionType::Exported,
+        ctx.new_type::<()>(),
+        &[],
+        "locals",
+        false,
+    );
+
+    let by_hand = func.new_local(None, int.make_const().make_pointer(), "by_hand");
+    assert!(TypedLValue::<&i32>::from_lvalue(by_hand).is_some());
+
+    let array = func.new_local(None, ctx.new_array_type(None, int, 4), "array");
+    assert!(TypedLValue::<[i32; 4]>::from_lvalue(array).is_some());
+    assert!(TypedLValue::<[i32; 5]>::from_lvalue(array).is_none());
+
+    let callback_ty = ctx.new_function_pointer_type(None, int, &[int], false);
+    let callback = func.new_local(None, callback_ty, "callback");
+    assert!(TypedLValue::<extern "C" fn(i32) -> i32>::from_lvalue(callback).is_some());
+    assert!(TypedLValue::<extern "C" fn(i32)>::from_lvalue(callback).is_none());
+}